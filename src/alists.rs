@@ -0,0 +1,194 @@
+use crate::context::{LispContext};
+use crate::eval::{apply_proc, eval_args, LispResult};
+use crate::tokens::{LispError, LispToken};
+
+// Association lists are lists of pairs such as ((a . 1) (b . 2)), looked up by the car of
+// each pair. Property lists are flat lists alternating keys and values such as (a 1 b 2),
+// with keys compared by eq?. Every symbol also has a property list of its own, read and
+// changed with get and put.
+//
+// Lists are values, so the functions changing an association or property list return a new
+// list rather than modifying the one given.
+
+// function: adds the association and property list functions to the given context.
+pub fn register(symbols: &mut LispContext) {
+    symbols.insert_func("assq", assq);
+    symbols.insert_func("assv", assv);
+    symbols.insert_func("assoc", assoc);
+    symbols.insert_func("alist-copy", alist_copy);
+    symbols.insert_func("alist-update", alist_update);
+    symbols.insert_func("plist-get", plist_get);
+    symbols.insert_func("plist-put", plist_put);
+    symbols.insert_func("get", get);
+    symbols.insert_func("put", put);
+    symbols.insert_func("symbol-plist", symbol_plist);
+}
+
+// enum: the equality test used to compare keys.
+// Proc: a procedure called with the key being looked up and the key of an entry.
+enum KeyTest<'a> {
+    Eq,
+    Eqv,
+    Equal,
+    Proc(&'a LispToken)
+}
+
+impl KeyTest<'_> {
+    fn matches(&self, ctx: &mut LispContext, key: &LispToken, other: &LispToken) -> Result<bool, LispError> {
+        match self {
+            KeyTest::Eq => Ok(key.is_eq(other)),
+            KeyTest::Eqv => Ok(key.is_eqv(other)),
+            KeyTest::Equal => Ok(key.is_equal(other)),
+            KeyTest::Proc(proc) => {
+                let result = apply_proc(ctx, proc, vec![key.clone(), other.clone()])?;
+                Ok(ctx.is_true(&result))
+            }
+        }
+    }
+}
+
+fn to_alist(token: &LispToken) -> Result<&[LispToken], LispError> {
+    match token {
+        LispToken::List(entries) if entries.iter().all(|entry| entry.is_pair()) => Ok(entries),
+        _ => Err(LispError::EvalError("value is not an association list.".to_string()))
+    }
+}
+
+fn to_plist(token: &LispToken) -> Result<&[LispToken], LispError> {
+    match token {
+        LispToken::List(xs) if xs.len() % 2 == 0 => Ok(xs),
+        _ => Err(LispError::EvalError("value is not a property list.".to_string()))
+    }
+}
+
+fn to_symbol(token: &LispToken) -> Result<&str, LispError> {
+    match token {
+        LispToken::Sym(s) => Ok(s),
+        _ => Err(LispError::EvalError("value is not a symbol.".to_string()))
+    }
+}
+
+// function: returns the position of the first entry of an association list whose key matches.
+fn find_entry(ctx: &mut LispContext, key: &LispToken, alist: &[LispToken], test: &KeyTest) -> Result<Option<usize>, LispError> {
+    for (idx, entry) in alist.iter().enumerate() {
+        if test.matches(ctx, key, entry.car().unwrap())? {
+            return Ok(Some(idx));
+        }
+    }
+
+    Ok(None)
+}
+
+// function: returns the first entry of the association list whose key matches, or #f.
+fn lookup(ctx: &mut LispContext, key: &LispToken, alist: &LispToken, test: &KeyTest) -> LispResult {
+    let alist = to_alist(alist)?;
+
+    match find_entry(ctx, key, alist, test)? {
+        Some(idx) => Ok(alist[idx].clone()),
+        None => Ok(LispToken::from(false))
+    }
+}
+
+fn assq(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    lookup(ctx, &xs[0], &xs[1], &KeyTest::Eq)
+}
+
+fn assv(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    lookup(ctx, &xs[0], &xs[1], &KeyTest::Eqv)
+}
+
+// function: looks a key up comparing with equal?, or with the procedure given as the third argument.
+fn assoc(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 3)?;
+
+    match xs.get(2) {
+        Some(proc) => lookup(ctx, &xs[0], &xs[1], &KeyTest::Proc(proc)),
+        None => lookup(ctx, &xs[0], &xs[1], &KeyTest::Equal)
+    }
+}
+
+// function: returns a copy of an association list with fresh pairs for its entries.
+fn alist_copy(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    let entries = to_alist(&xs[0])?.iter()
+        .map(|entry| LispToken::cons(entry.car().unwrap().clone(), entry.cdr().unwrap()))
+        .collect();
+
+    Ok(LispToken::List(entries))
+}
+
+// function: returns the association list with the value of the first entry matching the key
+// replaced, or with a new entry at the front when there is none. keys are compared with eqv?,
+// or with the procedure given as the fourth argument.
+fn alist_update(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 3, 4)?;
+    let mut alist = to_alist(&xs[2])?.to_vec();
+
+    let test = match xs.get(3) {
+        Some(proc) => KeyTest::Proc(proc),
+        None => KeyTest::Eqv
+    };
+
+    match find_entry(ctx, &xs[0], &alist, &test)? {
+        Some(idx) => alist[idx] = LispToken::cons(alist[idx].car().unwrap().clone(), xs[1].clone()),
+        None => alist.insert(0, LispToken::cons(xs[0].clone(), xs[1].clone()))
+    }
+
+    Ok(LispToken::List(alist))
+}
+
+// function: returns the value of a property, or the default or #f when it is missing.
+fn plist_lookup(plist: &LispToken, key: &LispToken, default: Option<&LispToken>) -> LispResult {
+    let plist = to_plist(plist)?;
+
+    match plist.chunks(2).find(|entry| entry[0].is_eq(key)) {
+        Some(entry) => Ok(entry[1].clone()),
+        None => Ok(default.cloned().unwrap_or_else(|| LispToken::from(false)))
+    }
+}
+
+// function: returns the property list with the property set to the value, appending it when missing.
+fn plist_with(plist: &LispToken, key: &LispToken, value: &LispToken) -> LispResult {
+    let mut plist = to_plist(plist)?.to_vec();
+
+    match plist.chunks(2).position(|entry| entry[0].is_eq(key)) {
+        Some(idx) => plist[2 * idx + 1] = value.clone(),
+        None => plist.extend(vec![key.clone(), value.clone()])
+    }
+
+    Ok(LispToken::List(plist))
+}
+
+fn plist_get(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 3)?;
+    plist_lookup(&xs[0], &xs[1], xs.get(2))
+}
+
+fn plist_put(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 3, 3)?;
+    plist_with(&xs[0], &xs[1], &xs[2])
+}
+
+// function: returns a property of a symbol, or the default or #f when it is missing.
+fn get(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 3)?;
+    let plist = ctx.plist(to_symbol(&xs[0])?);
+    plist_lookup(&plist, &xs[1], xs.get(2))
+}
+
+// function: sets a property of a symbol.
+fn put(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 3, 3)?;
+    let symbol = to_symbol(&xs[0])?;
+    let plist = plist_with(&ctx.plist(symbol), &xs[1], &xs[2])?;
+
+    ctx.set_plist(symbol, plist);
+    Ok(LispToken::nil())
+}
+
+fn symbol_plist(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(ctx.plist(to_symbol(&xs[0])?))
+}
@@ -0,0 +1,265 @@
+use crate::context::{LispContext};
+use crate::eval::{eval_args, eval_vec, LispResult};
+use crate::tokens::{LispError, LispToken};
+use crate::vectors::{check_index, range};
+
+use std::cell::{RefCell};
+use std::rc::{Rc};
+
+// Bytevectors are mutable sequences of bytes, written as #u8(1 2 255).
+//
+// The integer accessors such as (bytevector-u32-ref bv k 'little) read and write the bytes
+// starting at index k, in big endian order unless 'little is given. Since numbers are stored
+// as floating point values, 64 bit integers are only exact up to 2^53.
+
+// function: adds the bytevector library to the given context.
+pub fn register(symbols: &mut LispContext) {
+    symbols.insert_func("bytevector?", is_bytevector);
+    symbols.insert_func("make-bytevector", make_bytevector);
+    symbols.insert_func("bytevector", bytevector);
+    symbols.insert_func("bytevector-length", bytevector_length);
+    symbols.insert_func("bytevector-u8-ref", bytevector_u8_ref);
+    symbols.insert_func("bytevector-u8-set!", bytevector_u8_set);
+    symbols.insert_func("bytevector-copy", bytevector_copy);
+    symbols.insert_func("bytevector-copy!", bytevector_copy_into);
+    symbols.insert_func("bytevector-append", bytevector_append);
+    symbols.insert_func("utf8->string", utf8_to_string);
+    symbols.insert_func("string->utf8", string_to_utf8);
+    symbols.insert_func("bytevector-s8-ref", |ctx, args| int_ref(ctx, args, 1, true));
+    symbols.insert_func("bytevector-s8-set!", |ctx, args| int_set(ctx, args, 1, true));
+    symbols.insert_func("bytevector-u16-ref", |ctx, args| int_ref(ctx, args, 2, false));
+    symbols.insert_func("bytevector-s16-ref", |ctx, args| int_ref(ctx, args, 2, true));
+    symbols.insert_func("bytevector-u16-set!", |ctx, args| int_set(ctx, args, 2, false));
+    symbols.insert_func("bytevector-s16-set!", |ctx, args| int_set(ctx, args, 2, true));
+    symbols.insert_func("bytevector-u32-ref", |ctx, args| int_ref(ctx, args, 4, false));
+    symbols.insert_func("bytevector-s32-ref", |ctx, args| int_ref(ctx, args, 4, true));
+    symbols.insert_func("bytevector-u32-set!", |ctx, args| int_set(ctx, args, 4, false));
+    symbols.insert_func("bytevector-s32-set!", |ctx, args| int_set(ctx, args, 4, true));
+    symbols.insert_func("bytevector-u64-ref", |ctx, args| int_ref(ctx, args, 8, false));
+    symbols.insert_func("bytevector-s64-ref", |ctx, args| int_ref(ctx, args, 8, true));
+    symbols.insert_func("bytevector-u64-set!", |ctx, args| int_set(ctx, args, 8, false));
+    symbols.insert_func("bytevector-s64-set!", |ctx, args| int_set(ctx, args, 8, true));
+}
+
+// function: wraps the bytes in a new bytevector. every call creates a distinct object, so the
+// result must not be memoized.
+fn new_bytevector(ctx: &mut LispContext, bytes: Vec<u8>) -> LispToken {
+    ctx.record_effect();
+    LispToken::Bytes(Rc::new(RefCell::new(bytes)))
+}
+
+fn to_bytes(token: &LispToken) -> Result<&Rc<RefCell<Vec<u8>>>, LispError> {
+    match token {
+        LispToken::Bytes(bytes) => Ok(bytes),
+        _ => Err(LispError::EvalError("value is not a bytevector.".to_string()))
+    }
+}
+
+// function: reads the optional byte order of an integer accessor, either 'big or 'little.
+fn is_little_endian(token: Option<&LispToken>) -> Result<bool, LispError> {
+    match token {
+        None => Ok(false),
+        Some(t) if t.is_sym("big") => Ok(false),
+        Some(t) if t.is_sym("little") => Ok(true),
+        Some(t) => Err(LispError::EvalError(format!("unknown byte order `{}`.", t)))
+    }
+}
+
+fn is_bytevector(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(LispToken::from(to_bytes(&xs[0]).is_ok()))
+}
+
+// function: returns a bytevector of k bytes, all set to the fill value or 0.
+fn make_bytevector(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 2)?;
+
+    let fill = match xs.get(1) {
+        Some(fill) => fill.to_byte()?,
+        None => 0
+    };
+
+    let len = xs[0].to_index()?;
+    let mut bytes = Vec::new();
+
+    if bytes.try_reserve(len).is_err() {
+        return Err(LispError::EvalError(format!("cannot make a bytevector of length {}.", xs[0])));
+    }
+
+    bytes.resize(len, fill);
+    Ok(new_bytevector(ctx, bytes))
+}
+
+// function: returns a bytevector of the arguments.
+fn bytevector(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_vec(ctx, args)?;
+    let bytes = xs.iter().map(|x| x.to_byte()).collect::<Result<Vec<u8>, LispError>>()?;
+    Ok(new_bytevector(ctx, bytes))
+}
+
+fn bytevector_length(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    let len = to_bytes(&xs[0])?.borrow().len();
+    Ok(LispToken::from(len as f64))
+}
+
+fn bytevector_u8_ref(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let bytes = to_bytes(&xs[0])?.borrow();
+    let idx = check_index(xs[1].to_index()?, bytes.len())?;
+    Ok(LispToken::from(f64::from(bytes[idx])))
+}
+
+fn bytevector_u8_set(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 3, 3)?;
+
+    {
+        let mut bytes = to_bytes(&xs[0])?.borrow_mut();
+        let idx = check_index(xs[1].to_index()?, bytes.len())?;
+        bytes[idx] = xs[2].to_byte()?;
+    }
+
+    // memoized results may have read the old contents.
+    ctx.invalidate();
+    Ok(LispToken::nil())
+}
+
+// function: returns a new bytevector holding the bytes between the optional start and end indices.
+fn bytevector_copy(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 3)?;
+
+    let copy = {
+        let bytes = to_bytes(&xs[0])?.borrow();
+        let (start, end) = range(bytes.len(), xs.get(1), xs.get(2))?;
+        bytes[start..end].to_vec()
+    };
+
+    Ok(new_bytevector(ctx, copy))
+}
+
+// function: copies the bytes of the source between the optional start and end indices into
+// the target, starting at the given index.
+fn bytevector_copy_into(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 3, 5)?;
+
+    let source = {
+        let bytes = to_bytes(&xs[2])?.borrow();
+        let (start, end) = range(bytes.len(), xs.get(3), xs.get(4))?;
+        bytes[start..end].to_vec()
+    };
+
+    {
+        let mut target = to_bytes(&xs[0])?.borrow_mut();
+        let at = xs[1].to_index()?;
+
+        if at.checked_add(source.len()).is_none_or(|end| end > target.len()) {
+            return Err(LispError::EvalError(format!("cannot copy {} bytes to index {} of a bytevector of length {}.", source.len(), xs[1], target.len())));
+        }
+
+        target[at..at + source.len()].copy_from_slice(&source);
+    }
+
+    // memoized results may have read the old contents.
+    ctx.invalidate();
+    Ok(LispToken::nil())
+}
+
+fn bytevector_append(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_vec(ctx, args)?;
+    let mut result = Vec::new();
+
+    for x in &xs {
+        result.extend_from_slice(&to_bytes(x)?.borrow());
+    }
+
+    Ok(new_bytevector(ctx, result))
+}
+
+// function: decodes the bytes between the optional start and end indices as UTF-8.
+fn utf8_to_string(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 3)?;
+    let bytes = to_bytes(&xs[0])?.borrow();
+    let (start, end) = range(bytes.len(), xs.get(1), xs.get(2))?;
+
+    match std::str::from_utf8(&bytes[start..end]) {
+        Ok(s) => Ok(LispToken::Str(s.to_string())),
+        Err(err) => Err(LispError::EvalError(format!("invalid UTF-8: {}.", err)))
+    }
+}
+
+fn string_to_utf8(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    let bytes = xs[0].to_str()?.as_bytes().to_vec();
+    Ok(new_bytevector(ctx, bytes))
+}
+
+// function: returns the index range of the size byte integer starting at the index argument.
+fn int_range(bytes: &[u8], idx: &LispToken, size: usize) -> Result<(usize, usize), LispError> {
+    let start = idx.to_index()?;
+
+    if start.checked_add(size).is_none_or(|end| end > bytes.len()) {
+        return Err(LispError::EvalError(format!("cannot access {} bytes at index {} of a bytevector of length {}.", size, idx, bytes.len())));
+    }
+
+    Ok((start, start + size))
+}
+
+// function: reads an integer of the given size in bytes, optionally signed.
+fn int_ref(ctx: &mut LispContext, args: &[LispToken], size: usize, signed: bool) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 3)?;
+    let bytes = to_bytes(&xs[0])?.borrow();
+    let (start, end) = int_range(&bytes, &xs[1], size)?;
+    let little = is_little_endian(xs.get(2))?;
+
+    let mut n : u64 = 0;
+    for i in 0..size {
+        let byte = if little { bytes[end - 1 - i] } else { bytes[start + i] };
+        n = (n << 8) | u64::from(byte);
+    }
+
+    let bits = 64 - 8 * size as u32;
+
+    if signed {
+        Ok(LispToken::from(((n << bits) as i64 >> bits) as f64))
+    } else {
+        Ok(LispToken::from(n as f64))
+    }
+}
+
+// function: writes an integer of the given size in bytes, checking that it fits.
+fn int_set(ctx: &mut LispContext, args: &[LispToken], size: usize, signed: bool) -> LispResult {
+    let xs = eval_args(ctx, args, 3, 4)?;
+    let f = xs[2].to_float()?;
+    let little = is_little_endian(xs.get(3))?;
+
+    let bits = 8 * size as u32;
+    let (min, max) = if signed {
+        (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+    } else {
+        (0, (1i128 << bits) - 1)
+    };
+
+    let n = f as i128;
+    if f.fract() != 0.0 || n < min || n > max {
+        return Err(LispError::EvalError(format!("{} does not fit in {} bytes.", xs[2], size)));
+    }
+
+    {
+        let mut bytes = to_bytes(&xs[0])?.borrow_mut();
+        let (start, end) = int_range(&bytes, &xs[1], size)?;
+        let be = (n as u64).to_be_bytes();
+        let encoded = &be[8 - size..];
+
+        for i in 0..size {
+            if little {
+                bytes[end - 1 - i] = encoded[i];
+            } else {
+                bytes[start + i] = encoded[i];
+            }
+        }
+    }
+
+    // memoized results may have read the old contents.
+    ctx.invalidate();
+    Ok(LispToken::nil())
+}
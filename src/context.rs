@@ -1,21 +1,64 @@
-use crate::tokens::{LispToken};
+use crate::tokens::{LispFunc, LispToken};
 use std::collections::{HashMap};
 
+// enum: selects the conventions of the lisp dialect being followed.
+// Lisp: #f and nil, the empty list, are both false.
+// Scheme: only #f is false.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dialect {
+    Lisp,
+    Scheme
+}
+
+impl Dialect {
+    pub fn from_name(name: &str) -> Option<Dialect> {
+        match name {
+            "lisp" => Some(Dialect::Lisp),
+            "scheme" => Some(Dialect::Scheme),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dialect::Lisp => "lisp",
+            Dialect::Scheme => "scheme"
+        }
+    }
+}
+
 
 // Struct to represent the environment of the Lisp Interpreter
 // globals: A hashmap to store globally store defined symbols
-// locals: A hashmap to store results of previous computation
+// locals: A hashmap to store results of previous computation, invalidated whenever a global changes
+// version: A counter incremented on every side effect so results computed across one aren't stored
+// dialect: The dialect deciding which values count as false
+// plists: The property lists of symbols set with put
+// next_methods: For each generic function method being run, the methods left for call-next-method and the arguments
+// continuations: The ids of the continuations whose call/cc hasn't returned yet, and the id to give the next one
 #[derive(Clone)]
 pub struct LispContext {
     globals: HashMap<String, LispToken>,
-    locals: HashMap<String, LispToken>
+    locals: HashMap<String, LispToken>,
+    version: usize,
+    dialect: Dialect,
+    plists: HashMap<String, LispToken>,
+    next_methods: Vec<(Vec<LispToken>, Vec<LispToken>)>,
+    continuations: Vec<usize>,
+    next_continuation: usize
 }
 
 impl LispContext {
     pub fn new() -> LispContext {
         LispContext {
             globals: HashMap::new(),
-            locals: HashMap::new()
+            locals: HashMap::new(),
+            version: 0,
+            dialect: Dialect::Lisp,
+            plists: HashMap::new(),
+            next_methods: Vec::new(),
+            continuations: Vec::new(),
+            next_continuation: 0
         }
     }
 
@@ -25,6 +68,82 @@ impl LispContext {
 
     pub fn insert<T: Into<String>>(&mut self, k: T, v: LispToken) {
         self.globals.insert(k.into(), v);
+        self.invalidate();
+    }
+
+    pub fn insert_func(&mut self, k: &'static str, f: LispFunc) {
+        self.insert(k, LispToken::Func(k, f));
+    }
+
+    pub fn invalidate(&mut self) {
+        self.locals.clear();
+        self.record_effect();
+    }
+
+    // function: records a side effect that doesn't change any global, such as output.
+    pub fn record_effect(&mut self) {
+        self.version += 1;
+    }
+
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    pub fn set_dialect(&mut self, dialect: Dialect) {
+        self.dialect = dialect;
+        self.invalidate();
+    }
+
+    // function: checks whether a value counts as true under the current dialect.
+    pub fn is_true(&self, value: &LispToken) -> bool {
+        match self.dialect {
+            Dialect::Lisp => !(value.is_false() || value.is_nil()),
+            Dialect::Scheme => !value.is_false()
+        }
+    }
+
+    // function: returns the property list of a symbol, which is empty until a property is put.
+    pub fn plist(&self, symbol: &str) -> LispToken {
+        self.plists.get(symbol).cloned().unwrap_or_else(LispToken::nil)
+    }
+
+    pub fn set_plist(&mut self, symbol: &str, plist: LispToken) {
+        self.plists.insert(symbol.to_string(), plist);
+        self.invalidate();
+    }
+
+    // function: returns the methods left to call with call-next-method and the arguments of the
+    // innermost method being run.
+    pub fn next_methods(&self) -> Option<(&[LispToken], &[LispToken])> {
+        self.next_methods.last().map(|(methods, args)| (methods.as_slice(), args.as_slice()))
+    }
+
+    pub fn push_next_methods(&mut self, methods: Vec<LispToken>, args: Vec<LispToken>) {
+        self.next_methods.push((methods, args));
+    }
+
+    pub fn pop_next_methods(&mut self) {
+        self.next_methods.pop();
+    }
+
+    // function: returns the id of a new continuation, which can be escaped to until it is popped.
+    pub fn push_continuation(&mut self) -> usize {
+        let id = self.next_continuation;
+        self.next_continuation += 1;
+        self.continuations.push(id);
+        id
+    }
+
+    pub fn pop_continuation(&mut self) {
+        self.continuations.pop();
+    }
+
+    pub fn is_continuation_active(&self, id: usize) -> bool {
+        self.continuations.contains(&id)
     }
 
     pub fn clear_locals(&mut self) {
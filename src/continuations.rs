@@ -0,0 +1,94 @@
+use crate::context::{LispContext};
+use crate::eval::{apply_proc, eval, eval_args, LispResult};
+use crate::tokens::{LispError, LispToken};
+use crate::values::{make_values};
+
+// (call/cc (lambda (k) body...)) calls the procedure with the continuation of the call/cc
+// expression. Calling (k value...) anywhere within the body, however deeply nested, abandons
+// the rest of the body and makes call/cc return the values.
+//
+// Continuations are escaping only: the call unwinds the evaluation back to its call/cc by
+// returning a LispError::Escape, so a continuation can't be resumed once its call/cc has
+// returned, and calling it then is an error.
+//
+// (dynamic-wind before thunk after) and (unwind-protect expr cleanup...) run their cleanup
+// however the protected code is left: by returning, by an error or by escaping to a
+// continuation, all of which pass through them as the Result returned by eval.
+
+// function: adds call-with-current-continuation, dynamic-wind and unwind-protect to the given context.
+pub fn register(symbols: &mut LispContext) {
+    symbols.insert_func("call-with-current-continuation", call_cc);
+    symbols.insert_func("call/cc", call_cc);
+    symbols.insert_func("dynamic-wind", dynamic_wind);
+    symbols.insert_func("unwind-protect", unwind_protect);
+}
+
+// function: returns the procedure (lambda args (escape id args)) that escapes to the continuation.
+fn continuation(id: usize) -> LispToken {
+    let args = LispToken::Sym("args".to_string());
+    let call = vec![LispToken::Func("escape", escape), LispToken::from(id as f64), args.clone()];
+
+    LispToken::List(vec![LispToken::Sym("lambda".to_string()), args, LispToken::List(call)])
+}
+
+// function: calls the procedure with a new continuation, returning the values the continuation
+// is called with if it is, or else the value of the procedure.
+fn call_cc(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+
+    let id = ctx.push_continuation();
+    let result = apply_proc(ctx, &xs[0], vec![continuation(id)]);
+    ctx.pop_continuation();
+
+    match result {
+        Err(LispError::Escape(target, value)) if target == id => Ok(value),
+        result => result
+    }
+}
+
+// function: (escape id values) unwinds to the call/cc of the continuation with the given id.
+fn escape(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let id = xs[0].to_index()?;
+
+    if !ctx.is_continuation_active(id) {
+        return Err(LispError::EvalError("continuation called after its call/cc returned.".to_string()));
+    }
+
+    let values = match &xs[1] {
+        LispToken::List(values) => values.clone(),
+        _ => return Err(LispError::InvalidArguments)
+    };
+
+    Err(LispError::Escape(id, make_values(values)))
+}
+
+// function: calls the before, thunk and after procedures in turn, returning the value of the
+// thunk. after is called even when the thunk fails or escapes, and the failure is passed on
+// unless after fails as well.
+fn dynamic_wind(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 3, 3)?;
+
+    apply_proc(ctx, &xs[0], Vec::new())?;
+    let result = apply_proc(ctx, &xs[1], Vec::new());
+    apply_proc(ctx, &xs[2], Vec::new())?;
+
+    result
+}
+
+// function: evaluates the protected expression and then the cleanup expressions, returning the
+// value of the protected expression. the cleanup is evaluated even when the expression fails
+// or escapes.
+fn unwind_protect(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.is_empty() {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    let result = eval(ctx, &args[0]);
+
+    for expr in &args[1..] {
+        eval(ctx, expr)?;
+    }
+
+    result
+}
@@ -1,12 +1,32 @@
-use crate::context::{LispContext};
-use crate::tokens::{LispError, LispToken};
+use crate::context::{Dialect, LispContext};
+use crate::tokens::{LispError, LispToken, PrintMode};
+use crate::alists;
+use crate::bytevectors;
+use crate::continuations;
+use crate::format;
+use crate::generics;
+use crate::hashtables::{self, LispHash};
+use crate::lists;
+use crate::matching;
 use crate::parser::{parse};
+use crate::ports;
+use crate::promises;
+use crate::records;
+use crate::regexp;
+use crate::sorting;
+use crate::strings;
+use crate::values::{self, values_of};
+use crate::vectors;
+
+use std::cell::{RefCell};
+use std::collections::{HashMap};
+use std::rc::{Rc};
 
 use rustyline::{Editor};
 use rustyline::error::ReadlineError;
 
 // type alias: for repeated return types for lisp primitives.
-type LispResult = Result<LispToken, LispError>;
+pub(crate) type LispResult = Result<LispToken, LispError>;
 
 // struct: Stores the environment of which user entered lisp code executes.
 // ctx: LispContext for storing globally defined symbols and tail call optimisation.
@@ -36,11 +56,11 @@ impl LispEnv {
             };
 
             if line.is_empty() {
-                println!("");
+                println!();
                 continue;
             }
 
-            match parse(&line.chars().collect()) {
+            match parse(&line.chars().collect::<Vec<char>>()) {
                 Ok(expr) => self.eval(&expr),
                 Err(err) => self.result = format!("{}", err)
             }
@@ -61,7 +81,11 @@ impl LispEnv {
     // function: evaluates user lisp expression and stores the result in self
     fn eval(&mut self, expr: &LispToken) {
         match eval(&mut self.ctx, expr) {
-            Ok(res) => self.result = format!("{}", res),
+            // multiple values are printed one per line.
+            Ok(LispToken::Values(xs)) => {
+                self.result = xs.iter().map(|x| format!("{}", x.printed(PrintMode::Write))).collect::<Vec<_>>().join("\n > ")
+            },
+            Ok(res) => self.result = format!("{}", res.printed(PrintMode::Write)),
             Err(err) => {
                 self.status = !(err == LispError::Quit);
                 self.result = format!("{}", err)
@@ -76,38 +100,72 @@ impl Default for LispEnv {
 
         symbols.insert("#t", LispToken::from(true));
         symbols.insert("#f", LispToken::from(false));
-        symbols.insert("#nil", LispToken::from(false));
-
-        symbols.insert("+", LispToken::Func(add));
-        symbols.insert("-", LispToken::Func(sub));
-        symbols.insert("*", LispToken::Func(mul));
-        symbols.insert("/", LispToken::Func(div));
-        symbols.insert("mod", LispToken::Func(modulo));
-
-        symbols.insert(">", LispToken::Func(gt));
-        symbols.insert("<", LispToken::Func(lt));
-
-        symbols.insert("and", LispToken::Func(and));
-        symbols.insert("or", LispToken::Func(or));
-        symbols.insert("not", LispToken::Func(not));
-
-        symbols.insert("cons", LispToken::Func(cons));
-        symbols.insert("car", LispToken::Func(car));
-        symbols.insert("cdr", LispToken::Func(cdr));
-
-        symbols.insert("eq", LispToken::Func(eq));
-        symbols.insert("neq", LispToken::Func(neq));
-
-        symbols.insert("atom", LispToken::Func(atom));
-        symbols.insert("cond", LispToken::Func(cond));
-        symbols.insert("quote", LispToken::Func(quote));
-
-        symbols.insert("let", LispToken::Func(label));
-        symbols.insert("lambda", LispToken::Func(lambda));
-        symbols.insert("apply", LispToken::Func(apply));
-        symbols.insert("eval", LispToken::Func(
-            |ctx: &mut LispContext, args: &Vec<LispToken>| -> LispResult { eval(ctx, &args[0])}));
-        symbols.insert("quit", LispToken::Func(quit));
+        symbols.insert("#nil", LispToken::nil());
+
+        symbols.insert_func("+", add);
+        symbols.insert_func("-", sub);
+        symbols.insert_func("*", mul);
+        symbols.insert_func("/", div);
+        symbols.insert_func("mod", modulo);
+
+        symbols.insert_func(">", gt);
+        symbols.insert_func("<", lt);
+
+        symbols.insert_func("and", and);
+        symbols.insert_func("or", or);
+        symbols.insert_func("not", not);
+
+        symbols.insert_func("cons", cons);
+        symbols.insert_func("car", car);
+        symbols.insert_func("cdr", cdr);
+        symbols.insert_func("null?", null);
+
+        symbols.insert_func("eq?", eq);
+        symbols.insert_func("eqv?", eqv);
+        symbols.insert_func("equal?", equal);
+        symbols.insert_func("eq", eq);
+        symbols.insert_func("neq", neq);
+
+        symbols.insert_func("atom", atom);
+        symbols.insert_func("cond", cond);
+        symbols.insert_func("if", if_);
+        symbols.insert_func("when", when);
+        symbols.insert_func("unless", unless);
+        symbols.insert_func("case", case);
+        symbols.insert_func("quote", quote);
+
+        symbols.insert_func("do", do_);
+        symbols.insert_func("while", while_);
+        symbols.insert_func("dotimes", dotimes);
+        symbols.insert_func("dolist", dolist);
+
+        symbols.insert_func("let", label);
+        symbols.insert_func("set!", set);
+        symbols.insert_func("lambda", lambda);
+        symbols.insert_func("destructuring-bind", destructuring_bind);
+        symbols.insert_func("receive", receive);
+        symbols.insert_func("let-values", let_values);
+        symbols.insert_func("apply", apply);
+        symbols.insert_func("eval", eval_datum);
+        symbols.insert_func("dialect", dialect);
+        symbols.insert_func("quit", quit);
+
+        strings::register(&mut symbols);
+        ports::register(&mut symbols);
+        format::register(&mut symbols);
+        regexp::register(&mut symbols);
+        vectors::register(&mut symbols);
+        hashtables::register(&mut symbols);
+        bytevectors::register(&mut symbols);
+        alists::register(&mut symbols);
+        lists::register(&mut symbols);
+        sorting::register(&mut symbols);
+        promises::register(&mut symbols);
+        records::register(&mut symbols);
+        generics::register(&mut symbols);
+        matching::register(&mut symbols);
+        values::register(&mut symbols);
+        continuations::register(&mut symbols);
 
         LispEnv {
             ctx: symbols,
@@ -117,110 +175,368 @@ impl Default for LispEnv {
     }
 }
 
-fn eval(ctx: &mut LispContext, expr: &LispToken) -> LispResult {
-    match ctx.clone().get_local(format!("{}", expr)) {
-        Some(r) => return Ok(r.clone()),
-        _ => ()
-    }
-
-    let result = match expr {
-        LispToken::List(_) => {
+pub(crate) fn eval(ctx: &mut LispContext, expr: &LispToken) -> LispResult {
+    match expr {
+        // objects with identity print like any other object with equal contents, so expressions
+        // holding them can't be looked up by their printed form.
+        LispToken::List(_) if expr.has_identity() => {
             eval_list(ctx, expr)
         },
-        LispToken::Sym(s) => {
-            if let Some(sym) = ctx.clone().get(s.to_string()) {
-                if let LispToken::List(_) = &sym {
-                    return eval(ctx, sym);
-                }
-                return Ok(sym.clone());
+        LispToken::List(_) => {
+            let key = format!("{}", expr.printed(PrintMode::Write));
+
+            if let Some(r) = ctx.get_local(&key) {
+                return Ok(r.clone());
+            }
+
+            // results of expressions with side effects on the globals are not stored.
+            let version = ctx.version();
+            let result = eval_list(ctx, expr)?;
+
+            if ctx.version() == version {
+                ctx.insert_local(key, result.clone());
             }
 
-            Err(LispError::EvalError(format!("undefined symbol `{:?}`", expr.clone())))
+            Ok(result)
         },
-        LispToken::Num(_) => {
-            Ok(expr.clone())
+        LispToken::Sym(s) => {
+            match ctx.get(s) {
+                Some(sym) => Ok(sym.clone()),
+                None => Err(LispError::EvalError(format!("undefined symbol `{:?}`", expr)))
+            }
         },
         LispToken::Quote(token) => {
-            let tokens = &parse(&token.chars().collect())?;
-            eval(ctx, tokens)
+            Ok(*token.clone())
         },
-        LispToken::Str(_) => {
-            Ok(expr.clone())
+        // a literal of a mutable object evaluates to a new copy each time, so that changing one
+        // value doesn't change the expression it came from.
+        LispToken::Bytes(_) | LispToken::Hash(_) | LispToken::Vector(_) => {
+            ctx.record_effect();
+            Ok(copy_literal(expr))
         },
+        // all other tokens evaluate to themselves.
         _ => {
-            Err(LispError::EvalError(format!("unexpected expression [{}]", expr)))
+            Ok(expr.clone())
         }
-    }?;
-
-    ctx.insert_local(format!("{}", expr), result.clone());
-    Ok(result)
+    }
 }
 
 fn eval_list(ctx: &mut LispContext, expr: &LispToken) -> LispResult {
-    let mut lst = &Vec::new();
+    let lst = match expr {
+        LispToken::List(xs) if !xs.is_empty() => xs,
+        _ => return Ok(expr.clone())
+    };
 
-    match expr {
-        LispToken::List(xs) => lst = xs,
-        _ => {}
+    match eval(ctx, &lst[0])? {
+        LispToken::Func(_, func) => func(ctx, &lst[1..]),
+        LispToken::Generic(generic) => {
+            let args = eval_vec(ctx, &lst[1..])?;
+            generics::apply_generic(ctx, &generic, args)
+        },
+        LispToken::List(f) if is_lambda(&f) => {
+            let args = eval_vec(ctx, &lst[1..])?;
+            apply_lambda(ctx, &f, args)
+        },
+        head => {
+            // For all other tokens that aren't procedures, the list evaluates to its items.
+            let mut xs = vec![head];
+            xs.extend(eval_vec(ctx, &lst[1..])?);
+            Ok(LispToken::List(xs))
+        }
     }
+}
 
-    if lst.is_empty() {
-        return Ok(expr.clone());
+pub(crate) fn eval_vec(ctx: &mut LispContext, args: &[LispToken]) -> Result<Vec<LispToken>, LispError> {
+    let mut xs : Vec<LispToken> = Vec::new();
+
+    for arg in args {
+        let value = eval(ctx, arg)?;
+        xs.push(value);
     }
 
-    if let LispToken::List(test) = lst[0].clone() {
-        if test[0] == LispToken::Sym("lambda".to_string()) {
-            return lambda(ctx, &lst);
-        }
+    Ok(xs)
+}
+
+// function: evaluates the arguments and checks that there are between min and max of them.
+pub(crate) fn eval_args(ctx: &mut LispContext, args: &[LispToken], min: usize, max: usize) -> Result<Vec<LispToken>, LispError> {
+    if args.len() < min || args.len() > max {
+        return Err(LispError::InvalidNoArguments);
     }
 
-    if let Some(sym) = lst.iter().next() {
-        let symbol = eval(ctx, &sym.clone())?;
+    eval_vec(ctx, args)
+}
 
-        if let LispToken::Func(func) = symbol {
-            let v = lst.iter().skip(1).map(|tok| tok.clone()).collect();
-            return func(ctx, &v);
-        }
+// function: evaluates a sequence of expressions and returns the value of the last one.
+fn eval_body(ctx: &mut LispContext, body: &[LispToken]) -> LispResult {
+    let mut result = LispToken::nil();
 
-        // For all other tokens that aren't Func
+    for expr in body {
+        result = eval(ctx, expr)?;
+    }
 
-        let mut xs = Vec::new();
+    Ok(result)
+}
 
-        for item in lst.iter() {
-            let result = eval(ctx, item)?;
-            xs.push(result);
-        }
+// function: evaluates an expression and tests whether its value counts as true.
+fn eval_test(ctx: &mut LispContext, expr: &LispToken) -> Result<bool, LispError> {
+    let value = eval(ctx, expr)?;
+    Ok(ctx.is_true(&value))
+}
 
-        let token_xs = LispToken::List(xs);
+// function: checks whether a list is a lambda expression of the form (lambda (params) body...).
+fn is_lambda(f: &[LispToken]) -> bool {
+    f.len() >= 3 && f[0].is_sym("lambda")
+}
 
-        if format!("{}", token_xs).contains("lambda") {
-            return eval(ctx, &token_xs);
-        }
+// function: wraps values that would otherwise be evaluated again, or copied like a literal, in a Quote variant.
+pub(crate) fn quote_value(value: &LispToken) -> LispToken {
+    match value {
+        LispToken::DottedList(..) | LispToken::List(_) | LispToken::Sym(_) |
+        LispToken::Bytes(_) | LispToken::Hash(_) | LispToken::Vector(_) => LispToken::Quote(Box::new(value.clone())),
+        _ => value.clone()
+    }
+}
+
+// function: copies a vector, hash table or bytevector literal along with the literals nested in it.
+fn copy_literal(token: &LispToken) -> LispToken {
+    match token {
+        LispToken::Bytes(bytes) => LispToken::Bytes(Rc::new(RefCell::new(bytes.borrow().clone()))),
+        LispToken::Hash(table) => {
+            let table = table.borrow();
+            let mut copy = LispHash::new(table.test);
 
-        return Ok(token_xs);
+            for (key, value) in table.entries() {
+                copy.insert(copy_literal(key), copy_literal(value));
+            }
+
+            LispToken::Hash(Rc::new(RefCell::new(copy)))
+        },
+        LispToken::Vector(xs) => LispToken::Vector(Rc::new(RefCell::new(xs.borrow().iter().map(copy_literal).collect()))),
+        LispToken::List(xs) => LispToken::List(xs.iter().map(copy_literal).collect()),
+        LispToken::DottedList(xs, tail) => LispToken::DottedList(xs.iter().map(copy_literal).collect(), Box::new(copy_literal(tail))),
+        _ => token.clone()
     }
+}
 
-    let result = eval_vec(ctx, &lst)?;
-    Ok(LispToken::List(result))
+// function: replaces free occurrences of the bound symbols in an expression with their values.
+pub(crate) fn substitute(expr: &LispToken, bindings: &HashMap<String, LispToken>) -> LispToken {
+    match expr {
+        LispToken::Sym(s) => match bindings.get(s) {
+            Some(value) => quote_value(value),
+            None => expr.clone()
+        },
+        LispToken::List(xs) => {
+            let head = match xs.first() {
+                Some(LispToken::Sym(s)) => s.as_str(),
+                _ => ""
+            };
+
+            match head {
+                "quote" => expr.clone(),
+                // parameters of an inner lambda shadow the outer bindings.
+                "lambda" if is_lambda(xs) => {
+                    let inner = shadow(bindings, &xs[1..2]);
+                    substitute_from(xs, 2, &inner)
+                },
+                // (do ((var init step)...) (test expr...) body...)
+                "do" if xs.len() >= 3 => {
+                    let specs = match &xs[1] {
+                        LispToken::List(specs) => specs,
+                        _ => return expr.clone()
+                    };
+
+                    let names : Vec<LispToken> = specs.iter().filter_map(|spec| match spec {
+                        LispToken::List(spec) => spec.first().cloned(),
+                        _ => None
+                    }).collect();
+                    let inner = shadow(bindings, &names);
+
+                    let specs = specs.iter().map(|spec| match spec {
+                        LispToken::List(spec) if !spec.is_empty() => {
+                            let mut lst = vec![spec[0].clone()];
+                            lst.extend(spec.iter().skip(1).take(1).map(|x| substitute(x, bindings)));
+                            lst.extend(spec.iter().skip(2).map(|x| substitute(x, &inner)));
+                            LispToken::List(lst)
+                        },
+                        _ => spec.clone()
+                    }).collect();
+
+                    let mut lst = vec![xs[0].clone(), LispToken::List(specs)];
+                    lst.extend(xs.iter().skip(2).map(|x| substitute(x, &inner)));
+                    LispToken::List(lst)
+                },
+                // (dotimes (var count result) body...) and (dolist (var list result) body...)
+                "dotimes" | "dolist" if xs.len() >= 2 => {
+                    let spec = match &xs[1] {
+                        LispToken::List(spec) if !spec.is_empty() => spec,
+                        _ => return expr.clone()
+                    };
+
+                    let inner = shadow(bindings, &spec[..1]);
+                    let mut lst = vec![spec[0].clone()];
+                    lst.extend(spec.iter().skip(1).take(1).map(|x| substitute(x, bindings)));
+                    lst.extend(spec.iter().skip(2).map(|x| substitute(x, &inner)));
+
+                    let mut xs = substitute_from(xs, 2, &inner);
+                    if let LispToken::List(form) = &mut xs {
+                        form[1] = LispToken::List(lst);
+                    }
+                    xs
+                },
+                // (receive formals expr body...) and (destructuring-bind pattern expr body...)
+                "receive" | "destructuring-bind" if xs.len() >= 3 => {
+                    let inner = shadow(bindings, &xs[1..2]);
+                    let mut lst = vec![xs[0].clone(), xs[1].clone(), substitute(&xs[2], bindings)];
+                    lst.extend(xs.iter().skip(3).map(|x| substitute(x, &inner)));
+                    LispToken::List(lst)
+                },
+                // (let-values ((formals expr)...) body...)
+                "let-values" if xs.len() >= 2 => {
+                    let specs = match &xs[1] {
+                        LispToken::List(specs) => specs,
+                        _ => return expr.clone()
+                    };
+
+                    let names : Vec<LispToken> = specs.iter().filter_map(|spec| match spec {
+                        LispToken::List(spec) => spec.first().cloned(),
+                        _ => None
+                    }).collect();
+                    let inner = shadow(bindings, &names);
+
+                    let specs = specs.iter().map(|spec| match spec {
+                        LispToken::List(spec) if !spec.is_empty() => substitute_from(spec, 1, bindings),
+                        _ => spec.clone()
+                    }).collect();
+
+                    let mut lst = vec![xs[0].clone(), LispToken::List(specs)];
+                    lst.extend(xs.iter().skip(2).map(|x| substitute(x, &inner)));
+                    LispToken::List(lst)
+                },
+                // (match expr (pattern body...)...)
+                "match" if xs.len() >= 2 => matching::substitute_match(xs, bindings),
+                _ => LispToken::List(xs.iter().map(|x| substitute(x, bindings)).collect())
+            }
+        },
+        _ => expr.clone()
+    }
 }
 
-fn eval_vec(ctx: &mut LispContext, args: &Vec<LispToken>) -> Result<Vec<LispToken>, LispError> {
-    let mut xs : Vec<LispToken> = Vec::new();
+// function: removes the names bound by an inner form from the outer bindings. the names may be
+// parameter lists, including nested and dotted ones.
+fn shadow(bindings: &HashMap<String, LispToken>, names: &[LispToken]) -> HashMap<String, LispToken> {
+    let mut inner = bindings.clone();
 
-    for arg in args {
-        match arg {
-            LispToken::Quote(_) => xs.push(arg.clone()),
-            x => {
-                let value = eval(ctx, x)?;
-                xs.push(value.clone());
+    for name in names {
+        unbind(&mut inner, name);
+    }
+
+    inner
+}
+
+fn unbind(bindings: &mut HashMap<String, LispToken>, params: &LispToken) {
+    match params {
+        LispToken::Sym(s) => {
+            bindings.remove(s);
+        },
+        LispToken::List(xs) => xs.iter().for_each(|x| unbind(bindings, x)),
+        LispToken::DottedList(xs, rest) => {
+            xs.iter().for_each(|x| unbind(bindings, x));
+            unbind(bindings, rest);
+        },
+        _ => ()
+    }
+}
+
+// function: keeps the first n items of a form as they are and substitutes the remaining ones.
+fn substitute_from(xs: &[LispToken], n: usize, bindings: &HashMap<String, LispToken>) -> LispToken {
+    let mut lst : Vec<LispToken> = xs.iter().take(n).cloned().collect();
+    lst.extend(xs.iter().skip(n).map(|x| substitute(x, bindings)));
+    LispToken::List(lst)
+}
+
+// function: binds the parameters of a lambda to already evaluated arguments and evaluates its body.
+// the parameters are destructured like the pattern of destructuring-bind, so (lambda ((a b) c) ...)
+// takes a list of two items and another value, and (lambda (a . rest) ...) takes any extra
+// arguments as a list.
+fn apply_lambda(ctx: &mut LispContext, f: &[LispToken], args: Vec<LispToken>) -> LispResult {
+    // checks if the number of input values matches the number of parameters
+    match &f[1] {
+        LispToken::List(params) if args.len() != params.len() => return Err(LispError::InvalidNoArguments),
+        LispToken::DottedList(params, _) if args.len() < params.len() => return Err(LispError::InvalidNoArguments),
+        LispToken::List(_) | LispToken::DottedList(..) | LispToken::Sym(_) => (),
+        _ => return Err(LispError::InvalidArguments)
+    }
+
+    let mut bindings = HashMap::new();
+    destructure(&f[1], &LispToken::List(args), &mut bindings)?;
+
+    // replace expression parameters with input values.
+    let body : Vec<LispToken> = f[2..].iter().map(|x| substitute(x, &bindings)).collect();
+
+    // evaluate and return the result of the computed lambda.
+    eval_body(ctx, &body)
+}
+
+// function: binds the symbols of a pattern to the parts of the value in the same position. a
+// list pattern takes a list of as many items, and the tail of a dotted pattern or a pattern
+// that is a single symbol takes the rest of the list.
+fn destructure(pattern: &LispToken, value: &LispToken, bindings: &mut HashMap<String, LispToken>) -> Result<(), LispError> {
+    let mismatch = || LispError::EvalError(format!("cannot destructure `{}` with `{}`.", value, pattern));
+
+    match pattern {
+        LispToken::Sym(s) => {
+            bindings.insert(s.to_string(), value.clone());
+            Ok(())
+        },
+        LispToken::List(params) => match value {
+            LispToken::List(xs) if xs.len() == params.len() => {
+                for (param, x) in params.iter().zip(xs) {
+                    destructure(param, x, bindings)?;
+                }
+
+                Ok(())
+            },
+            _ => Err(mismatch())
+        },
+        LispToken::DottedList(params, rest) => {
+            let mut value = value.clone();
+
+            for param in params {
+                let (head, tail) = match (value.car(), value.cdr()) {
+                    (Some(head), Some(tail)) if value.is_pair() => (head.clone(), tail),
+                    _ => return Err(mismatch())
+                };
+
+                destructure(param, &head, bindings)?;
+                value = tail;
             }
-        }
+
+            destructure(rest, &value, bindings)
+        },
+        _ => Err(LispError::EvalError(format!("invalid parameter `{}`.", pattern)))
     }
+}
 
-    Ok(xs)
+// function: evaluates a body with the bound symbols replaced by their values.
+fn eval_bound(ctx: &mut LispContext, bindings: &HashMap<String, LispToken>, body: &[LispToken]) -> LispResult {
+    let body : Vec<LispToken> = body.iter().map(|x| substitute(x, bindings)).collect();
+    eval_body(ctx, &body)
+}
+
+// function: calls a builtin function or lambda with already evaluated arguments.
+pub(crate) fn apply_proc(ctx: &mut LispContext, proc: &LispToken, args: Vec<LispToken>) -> LispResult {
+    match proc {
+        LispToken::Func(_, func) => {
+            let xs : Vec<LispToken> = args.iter().map(quote_value).collect();
+            func(ctx, &xs)
+        },
+        LispToken::Generic(generic) => generics::apply_generic(ctx, generic, args),
+        LispToken::List(f) if is_lambda(f) => apply_lambda(ctx, f, args),
+        _ => Err(LispError::EvalError(format!("`{}` is not a procedure", proc)))
+    }
 }
 
-fn add(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
+fn add(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
     let lst = eval_vec(ctx, args)?;
     let xs = LispToken::to_vec_float(&lst)?;
 
@@ -232,7 +548,7 @@ fn add(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
     Ok(LispToken::from(result))
 }
 
-fn sub(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
+fn sub(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
     let lst = eval_vec(ctx, args)?;
     let xs = LispToken::to_vec_float(&lst)?;
 
@@ -244,7 +560,7 @@ fn sub(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
     Ok(LispToken::from(xs[0] - value))
 }
 
-fn mul(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
+fn mul(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
     let lst = eval_vec(ctx, args)?;
     let xs = LispToken::to_vec_float(&lst)?;
 
@@ -254,13 +570,13 @@ fn mul(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
 
     let mut result : f64 = xs[0];
     for value in xs.iter().skip(1) {
-        result = result * value;
+        result *= value;
     }
 
     Ok(LispToken::from(result))
 }
 
-fn div(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
+fn div(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
     let lst = eval_vec(ctx, args)?;
     let xs = LispToken::to_vec_float(&lst)?;
 
@@ -270,13 +586,13 @@ fn div(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
 
     let mut result : f64 = xs[0];
     for value in xs.iter().skip(1) {
-        result = result / value;
+        result /= value;
     }
 
     Ok(LispToken::from(result))
 }
 
-fn modulo(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
+fn modulo(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
     let lst = eval_vec(ctx, args)?;
     let xs = LispToken::to_vec_float(&lst)?;
 
@@ -284,17 +600,10 @@ fn modulo(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
         return Err(LispError::InvalidNoArguments);
     }
 
-    let mut result : f64 = xs[0];
-
-    match xs.iter().skip(1).next() {
-        Some(value) => result = result % value,
-        _ => return Err(LispError::InvalidNoArguments)
-    }
-
-    Ok(LispToken::from(result))
+    Ok(LispToken::from(xs[0] % xs[1]))
 }
 
-fn lt(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
+fn lt(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
     let lst = eval_vec(ctx, args)?;
     let xs = LispToken::to_vec_float(&lst)?;
 
@@ -305,7 +614,7 @@ fn lt(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
     Ok(LispToken::from(xs[0] < xs[1]))
 }
 
-fn gt(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
+fn gt(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
     let lst = eval_vec(ctx, args)?;
     let xs = LispToken::to_vec_float(&lst)?;
 
@@ -316,249 +625,553 @@ fn gt(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
     Ok(LispToken::from(xs[0] > xs[1]))
 }
 
-fn and(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
-    let lst = eval_vec(ctx, args)?;
-    let xs = LispToken::to_vec_bool(&lst)?;
+// function: evaluates arguments from left to right, stopping at the first false value.
+// returns the value of the last argument evaluated, or #t when no arguments are given.
+fn and(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let mut result = LispToken::from(true);
 
-    if xs.len() < 2 {
+    for (idx, arg) in args.iter().enumerate() {
+        result = eval(ctx, arg)?;
+
+        if idx + 1 < args.len() && !ctx.is_true(&result) {
+            break;
+        }
+    }
+
+    Ok(result)
+}
+
+// function: evaluates arguments from left to right, stopping at the first true value.
+// returns the value of the last argument evaluated, or #f when no arguments are given.
+fn or(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let mut result = LispToken::from(false);
+
+    for (idx, arg) in args.iter().enumerate() {
+        result = eval(ctx, arg)?;
+
+        if idx + 1 < args.len() && ctx.is_true(&result) {
+            break;
+        }
+    }
+
+    Ok(result)
+}
+
+fn not(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() != 1 {
         return Err(LispError::InvalidNoArguments);
     }
 
-    Ok(LispToken::from(!xs.contains(&false)))
+    let value = eval(ctx, &args[0])?;
+    Ok(LispToken::from(!ctx.is_true(&value)))
 }
 
-fn or(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
-    let lst = eval_vec(ctx, args)?;
-    let xs = LispToken::to_vec_bool(&lst)?;
+// function: prepends the leading arguments to the list given as the last argument.
+// when the last argument is not a list, the result is a dotted list such as (1 2 . 3).
+fn cons(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let mut xs = eval_vec(ctx, args)?;
 
-    if xs.len() < 2 {
+    let mut result = match xs.pop() {
+        Some(last) => last,
+        None => return Err(LispError::InvalidNoArguments)
+    };
+
+    while let Some(head) = xs.pop() {
+        result = LispToken::cons(head, result);
+    }
+
+    Ok(result)
+}
+
+fn car(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() != 1 {
         return Err(LispError::InvalidNoArguments);
     }
 
-    Ok(LispToken::from(xs.contains(&true)))
+    match eval(ctx, &args[0])?.car() {
+        Some(head) => Ok(head.clone()),
+        None => Ok(LispToken::nil())
+    }
 }
 
-fn not(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
-    let lst = eval_vec(ctx, args)?;
-    let xs = LispToken::to_vec_bool(&lst)?;
+fn cdr(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() != 1 {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    match eval(ctx, &args[0])?.cdr() {
+        Some(tail) => Ok(tail),
+        None => Ok(LispToken::nil())
+    }
+}
 
-    if xs.len() != 1 {
+// function: checks whether a value is the empty list.
+fn null(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() != 1 {
         return Err(LispError::InvalidNoArguments);
     }
 
-    Ok(LispToken::from(!xs[0]))
+    let value = eval(ctx, &args[0])?;
+    Ok(LispToken::from(value.is_nil()))
 }
 
-fn cons(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
-    let xs = eval_vec(ctx, args)?;
+fn atom(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() != 1 {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    let value = eval(ctx, &args[0])?;
+    Ok(LispToken::from(!value.is_pair()))
+}
+
+// function: evaluates the body of a matching clause, passing the tested value to the
+// procedure instead when the clause is of the form (test => proc).
+fn eval_clause(ctx: &mut LispContext, value: LispToken, body: &[LispToken]) -> LispResult {
+    if body.is_empty() {
+        return Ok(value);
+    }
 
-    if xs.len() < 1 {
+    if body[0].is_sym("=>") {
+        if body.len() != 2 {
+            return Err(LispError::EvalError("malformed expression.".to_string()));
+        }
+
+        let proc = eval(ctx, &body[1])?;
+        return apply_proc(ctx, &proc, vec![value]);
+    }
+
+    eval_body(ctx, body)
+}
+
+// function: evaluates each clause (test body...) in order until a test is true.
+// an else clause always matches, and a clause without a body returns the value of its test.
+fn cond(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.is_empty() {
         return Err(LispError::InvalidNoArguments);
     }
 
-    Ok(LispToken::List(xs))
+    for arg in args {
+        let lst = match arg {
+            LispToken::List(lst) if !lst.is_empty() => lst,
+            _ => return Err(LispError::EvalError("malformed expression.".to_string()))
+        };
+
+        if lst[0].is_sym("else") {
+            return eval_body(ctx, &lst[1..]);
+        }
+
+        let temp = eval(ctx, &lst[0])?;
+        if ctx.is_true(&temp) {
+            return eval_clause(ctx, temp, &lst[1..]);
+        }
+    }
+
+    Ok(LispToken::nil())
 }
 
-fn car(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
-    match eval(ctx, &args[0])? {
-        LispToken::List(lst) => {
-            if lst.is_empty() {
-                return Ok(LispToken::Sym("#nil".to_string()));
-            }
+// function: evaluates the consequent when the test is true and the optional alternative otherwise.
+fn if_(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() != 2 && args.len() != 3 {
+        return Err(LispError::InvalidNoArguments);
+    }
 
-            let lst = eval_vec(ctx, &lst)?;
-            Ok(lst[0].clone())
-        },
-        _ => Ok(LispToken::Sym("#nil".to_string()))
+    if eval_test(ctx, &args[0])? {
+        eval(ctx, &args[1])
+    } else if args.len() == 3 {
+        eval(ctx, &args[2])
+    } else {
+        Ok(LispToken::nil())
     }
 }
 
-fn cdr(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
-    match eval(ctx, &args[0])? {
-        LispToken::List(lst) => {
-            if lst.len() < 1 {
-                return Ok(LispToken::Sym("#nil".to_string()));
-            }
+// function: evaluates the body when the test is true.
+fn when(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.is_empty() {
+        return Err(LispError::InvalidNoArguments);
+    }
 
-            let lst = eval_vec(ctx, &lst)?;
-            Ok(LispToken::List(lst.iter().cloned().skip(1).collect()))
-        },
-        _ => Ok(LispToken::Sym("#nil".to_string()))
+    if eval_test(ctx, &args[0])? {
+        return eval_body(ctx, &args[1..]);
     }
+
+    Ok(LispToken::nil())
 }
 
-fn atom(_ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
-    if args.len() != 1 {
+// function: evaluates the body when the test is false.
+fn unless(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.is_empty() {
         return Err(LispError::InvalidNoArguments);
     }
 
-    match args[0] {
-        LispToken::List(_) => Ok(LispToken::from(false)),
-        _ => Ok(LispToken::from(true))
+    if !eval_test(ctx, &args[0])? {
+        return eval_body(ctx, &args[1..]);
     }
+
+    Ok(LispToken::nil())
 }
 
-fn cond(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
-    if args.len() < 1 {
+// function: evaluates the key and selects the first clause ((datum...) body...) listing it.
+// an else clause always matches, and (datums => proc) passes the key to the procedure.
+fn case(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.is_empty() {
         return Err(LispError::InvalidNoArguments);
     }
 
-    for arg in args {
-        if let LispToken::List(lst) = &arg {
-            if lst.len() != 2 {
+    let key = eval(ctx, &args[0])?;
+
+    for arg in args.iter().skip(1) {
+        let lst = match arg {
+            LispToken::List(lst) if !lst.is_empty() => lst,
+            _ => return Err(LispError::EvalError("malformed expression.".to_string()))
+        };
+
+        let matched = match &lst[0] {
+            LispToken::List(data) => data.iter().any(|datum| datum.is_eqv(&key)),
+            x if x.is_sym("else") => true,
+            _ => return Err(LispError::EvalError("malformed expression.".to_string()))
+        };
+
+        if matched {
+            if lst.len() == 1 {
                 return Err(LispError::EvalError("malformed expression.".to_string()));
             }
 
-            let temp = eval(ctx, &lst[0])?;
-            if temp.to_bool()? {
-                return eval(ctx, &lst[1]);
-            }
+            return eval_clause(ctx, key, &lst[1..]);
         }
     }
 
-    Ok(LispToken::Sym("#nil".to_string()))
+    Ok(LispToken::nil())
 }
 
-fn eq(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
-    let lst = eval_vec(ctx, args)?;
+// function: evaluates (do ((var init step)...) (test expr...) body...) iteratively. each
+// iteration rebinds the variables to the values of their step expressions until the test is true.
+fn do_(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() < 2 {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    let (specs, exit) = match (&args[0], &args[1]) {
+        (LispToken::List(specs), LispToken::List(exit)) if !exit.is_empty() => (specs, exit),
+        _ => return Err(LispError::EvalError("malformed expression.".to_string()))
+    };
+
+    let mut steps = Vec::new();
+    let mut bindings = HashMap::new();
+
+    for spec in specs {
+        match spec {
+            LispToken::List(spec) if spec.len() == 2 || spec.len() == 3 => {
+                let name = match &spec[0] {
+                    LispToken::Sym(s) => s.to_string(),
+                    _ => return Err(LispError::InvalidArguments)
+                };
+
+                let value = eval(ctx, &spec[1])?;
+                bindings.insert(name.clone(), value);
+
+                if let Some(step) = spec.get(2) {
+                    steps.push((name, step));
+                }
+            },
+            _ => return Err(LispError::EvalError("malformed expression.".to_string()))
+        }
+    }
+
+    loop {
+        if eval_test(ctx, &substitute(&exit[0], &bindings))? {
+            let body : Vec<LispToken> = exit[1..].iter().map(|x| substitute(x, &bindings)).collect();
+            return eval_body(ctx, &body);
+        }
+
+        for expr in &args[2..] {
+            eval(ctx, &substitute(expr, &bindings))?;
+        }
+
+        let mut next = bindings.clone();
+        for (name, step) in &steps {
+            let value = eval(ctx, &substitute(step, &bindings))?;
+            next.insert(name.to_string(), value);
+        }
+
+        bindings = next;
+    }
+}
+
+// function: evaluates the body repeatedly for as long as the test is true.
+fn while_(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.is_empty() {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    while eval_test(ctx, &args[0])? {
+        eval_body(ctx, &args[1..])?;
+    }
+
+    Ok(LispToken::nil())
+}
+
+// function: reads the (var value result) header shared by dotimes and dolist.
+fn loop_spec(args: &[LispToken]) -> Result<(String, &LispToken, Option<&LispToken>), LispError> {
+    if args.is_empty() {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    match &args[0] {
+        LispToken::List(spec) if spec.len() == 2 || spec.len() == 3 => match &spec[0] {
+            LispToken::Sym(s) => Ok((s.to_string(), &spec[1], spec.get(2))),
+            _ => Err(LispError::InvalidArguments)
+        },
+        _ => Err(LispError::EvalError("malformed expression.".to_string()))
+    }
+}
+
+// function: evaluates the body once for each value from 0 to count - 1 bound to var.
+// the optional result expression is evaluated with var bound to count.
+fn dotimes(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let (name, count, result) = loop_spec(args)?;
+    let count = eval(ctx, count)?.to_float()?.floor();
+
+    let mut bindings = HashMap::new();
+    let mut idx = 0.0;
+
+    while idx < count {
+        bindings.insert(name.clone(), LispToken::from(idx));
+
+        for expr in &args[1..] {
+            eval(ctx, &substitute(expr, &bindings))?;
+        }
+
+        idx += 1.0;
+    }
+
+    match result {
+        Some(expr) => {
+            bindings.insert(name, LispToken::from(count.max(0.0)));
+            eval(ctx, &substitute(expr, &bindings))
+        },
+        None => Ok(LispToken::nil())
+    }
+}
+
+// function: evaluates the body once for each item of the list bound to var.
+fn dolist(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let (name, lst, result) = loop_spec(args)?;
+
+    let items = match eval(ctx, lst)? {
+        LispToken::List(xs) => xs,
+        _ => return Err(LispError::EvalError("value is not a list.".to_string()))
+    };
+
+    let mut bindings = HashMap::new();
+
+    for item in items {
+        bindings.insert(name.clone(), item);
+
+        for expr in &args[1..] {
+            eval(ctx, &substitute(expr, &bindings))?;
+        }
+    }
+
+    // as in common lisp, the variable is bound to nil while the result form is evaluated.
+    match result {
+        Some(expr) => {
+            bindings.insert(name, LispToken::nil());
+            eval(ctx, &substitute(expr, &bindings))
+        },
+        None => Ok(LispToken::nil())
+    }
+}
+
+// function: reads the two arguments compared by the equality predicates.
+fn eval_pair(ctx: &mut LispContext, args: &[LispToken]) -> Result<(LispToken, LispToken), LispError> {
+    let mut lst = eval_vec(ctx, args)?;
 
     if lst.len() != 2 {
         return Err(LispError::InvalidNoArguments);
     }
 
-    Ok(LispToken::from(lst[0] == lst[1]))
+    let b = lst.pop().unwrap();
+    let a = lst.pop().unwrap();
+    Ok((a, b))
+}
+
+// function: identity equality, also available as eq.
+fn eq(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let (a, b) = eval_pair(ctx, args)?;
+    Ok(LispToken::from(a.is_eq(&b)))
+}
+
+// function: value equality, comparing numbers by value.
+fn eqv(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let (a, b) = eval_pair(ctx, args)?;
+    Ok(LispToken::from(a.is_eqv(&b)))
 }
 
-fn neq(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
+// function: deep structural equality.
+fn equal(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let (a, b) = eval_pair(ctx, args)?;
+    Ok(LispToken::from(a.is_equal(&b)))
+}
+
+fn neq(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
     let temp = eq(ctx, args)?;
-    not(ctx, &vec![temp])
+    not(ctx, &[temp])
 }
 
-fn quote(_ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
+fn quote(_ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
     if args.len() != 1 {
         return Err(LispError::InvalidNoArguments);
     }
 
-    Ok(LispToken::Quote(format!("'{}", args[0])))
+    Ok(args[0].clone())
 }
 
-fn label(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
+fn label(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
     if args.len() != 2 {
         return Err(LispError::InvalidNoArguments);
     }
 
     if let LispToken::Sym(s) = &args[0] {
-        if format!("{:?}", &args[1]).contains("Quote") {
-            let value = parse(&format!("{}", &args[1]).chars().collect())?;
-            ctx.insert(s.to_string(), value.clone());
-            return Ok(value);
-        }
-
         let value = eval(ctx, &args[1])?;
-        let result = eval(ctx, &value)?;
-
-        ctx.insert(s.to_string(), result);
+        ctx.insert(s.to_string(), value.clone());
         return Ok(value);
     }
 
     Err(LispError::InvalidArguments)
 }
 
-fn lambda(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
-    if args.len() == 2 {
-        if let LispToken::List(lst) = &args[0] {
-            if let LispToken::Sym(s) = &lst[0] {
-                if s == "lambda" {
-                    return apply(ctx, args);
-                }
-            }
-        }
+// function: assigns a new value to a symbol that has already been defined. only globals can be
+// assigned: the variables bound by lambda, do, dotimes, dolist and the other binding forms are
+// replaced by their values in the body, so the target of a set! on one of them is no longer a symbol.
+fn set(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() != 2 {
+        return Err(LispError::InvalidNoArguments);
     }
 
-    let mut lst = vec![LispToken::Sym("lambda".to_string())];
-    for token in args {
-        lst.push(token.clone());
+    let s = match &args[0] {
+        LispToken::Sym(s) => s,
+        target => return Err(LispError::EvalError(format!("cannot set! `{}`: local variables are replaced by their values and can't be assigned.", target)))
+    };
+
+    if ctx.get(s).is_none() {
+        return Err(LispError::EvalError(format!("undefined symbol `{:?}`", args[0])));
     }
 
+    let value = eval(ctx, &args[1])?;
+    ctx.insert(s.to_string(), value.clone());
+    Ok(value)
+}
+
+fn lambda(_ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() < 2 {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    let mut lst = vec![LispToken::Sym("lambda".to_string())];
+    lst.extend(args.iter().cloned());
+
     Ok(LispToken::List(lst))
 }
 
-// function: applies arguments to functions:
-fn apply(ctx: &mut LispContext, args: &Vec<LispToken>) -> LispResult {
-    if args.len() != 2 {
+// function: (destructuring-bind pattern expr body...) binds the symbols of the pattern to the
+// matching parts of the value of expr, as in (destructuring-bind (a (b c) . rest) lst ...).
+fn destructuring_bind(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() < 3 {
         return Err(LispError::InvalidNoArguments);
     }
 
-    // check if first element is a symbol
-    let symbol = match args[0] {
-        LispToken::List(_) => Some(args[0].clone()),
-        _ => match ctx.get(format!("{}", args[0])) {
-            Some(f) => Some(f.clone()),
-            None => None
-        }
-    };
+    let value = eval(ctx, &args[1])?;
+    let mut bindings = HashMap::new();
+    destructure(&args[0], &value, &mut bindings)?;
+    eval_bound(ctx, &bindings, &args[2..])
+}
 
-    // check if second element is a list.
-    let arguments = match &eval(ctx, &args[1])? {
-        LispToken::List(xs) => xs.to_vec(),
-        x => vec![x.clone()]
+// function: (receive formals expr body...) binds the values of expr to the formals, which are
+// a parameter list as in lambda.
+fn receive(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() < 3 {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    let value = eval(ctx, &args[1])?;
+    let mut bindings = HashMap::new();
+    destructure(&args[0], &LispToken::List(values_of(value)), &mut bindings)?;
+    eval_bound(ctx, &bindings, &args[2..])
+}
+
+// function: (let-values ((formals expr)...) body...) binds the values of each expression to its
+// formals like receive. the expressions are all evaluated before any of the bindings are made.
+fn let_values(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() < 2 {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    let specs = match &args[0] {
+        LispToken::List(specs) => specs,
+        _ => return Err(LispError::InvalidArguments)
     };
 
-    // check if first element evaluates to a list.
-    if let Some(LispToken::List(f)) = symbol {
-        if f.len() != 3 {
-            return Err(LispError::InvalidNoArguments);
-        }
+    let mut bindings = HashMap::new();
 
-        // checks if list is a lambda function.
-        if LispToken::Sym("lambda".to_string()) != f[0] {
-            return Err(LispError::InvalidArguments)
+    for spec in specs {
+        match spec {
+            LispToken::List(spec) if spec.len() == 2 => {
+                let value = eval(ctx, &spec[1])?;
+                destructure(&spec[0], &LispToken::List(values_of(value)), &mut bindings)?;
+            },
+            _ => return Err(LispError::EvalError(format!("invalid let-values binding `{}`.", spec)))
         }
+    }
 
-        // check parameters to lambda function.
-        let params : Vec<LispToken> = match &f[1] {
-            LispToken::List(xs) => xs.to_vec(),
-            _ => vec![]
-        };
+    eval_bound(ctx, &bindings, &args[1..])
+}
 
-        // get the expression to be computed.
-        let expr = match &f[2] {
-            LispToken::List(xs) => xs.to_vec(),
-            _ => vec![]
-        };
+// function: applies arguments to functions:
+fn apply(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() != 2 {
+        return Err(LispError::InvalidNoArguments);
+    }
 
-        // checks the length of the expression to be evaluated.
-        if expr.len() == 0 {
-            return Ok(LispToken::Sym("#nil".to_string()));
-        }
+    let proc = eval(ctx, &args[0])?;
 
-        // checks if the number of input values matches the number of parameters
-        if arguments.len() != params.len() {
-            return Err(LispError::InvalidNoArguments);
-        }
+    // check if second element is a list.
+    let arguments = match eval(ctx, &args[1])? {
+        LispToken::List(xs) => xs,
+        x => vec![x]
+    };
 
-        // convert expression to string using display trait.
-        let mut s = format!("{}", f[2]);
+    apply_proc(ctx, &proc, arguments)
+}
 
-        // replace expression parameters with input values.
-        for (idx, arg) in params.iter().enumerate() {
-            s = s.replace(&format!("{}", arg), &format!("{}", arguments[idx]));
-        }
+// function: evaluates its argument to obtain an expression and evaluates that expression.
+fn eval_datum(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() != 1 {
+        return Err(LispError::InvalidNoArguments);
+    }
 
-        // turn the string back into a lisp AST.
-        let expr = parse(&s.chars().collect())?;
+    let expr = eval(ctx, &args[0])?;
+    eval(ctx, &expr)
+}
 
-        // evaluate and return the result of the computed lambda.
-        return eval(ctx, &expr);
+// function: returns the current dialect, switching to the given one first when an argument is passed.
+// (dialect 'lisp) treats the empty list as false, while (dialect 'scheme) only treats #f as false.
+fn dialect(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() > 1 {
+        return Err(LispError::InvalidNoArguments);
     }
 
-    if let Some(LispToken::Func(func)) = symbol {
-        return func(ctx, &arguments);
+    if let Some(arg) = args.first() {
+        let value = eval(ctx, arg)?;
+
+        match Dialect::from_name(&format!("{}", value)) {
+            Some(dialect) => ctx.set_dialect(dialect),
+            None => return Err(LispError::EvalError(format!("unknown dialect `{}`", value)))
+        }
     }
 
-    return Err(LispError::InvalidArguments);
+    Ok(LispToken::Sym(ctx.dialect().name().to_string()))
 }
 
-fn quit(_ctx: &mut LispContext, _args: &Vec<LispToken>) -> LispResult {
+fn quit(_ctx: &mut LispContext, _args: &[LispToken]) -> LispResult {
     Err(LispError::Quit)
 }
@@ -0,0 +1,233 @@
+use crate::context::{LispContext};
+use crate::eval::{eval_vec, LispResult};
+use crate::ports::{write_to};
+use crate::tokens::{LispError, LispToken, PrintMode};
+
+// Format strings copy their text to the output and replace directives starting with ~:
+//   ~a  the next argument as printed by display     ~s  the next argument as printed by write
+//   ~d  an integer in decimal                        ~x ~o ~b  an integer in hex, octal or binary
+//   ~c  a character                                  ~w,df  a number with d digits after the point
+//   ~%  a newline                                    ~~  a tilde
+// A directive may be given a minimum width and padding character, as in ~10a or ~8,'0d.
+// ~a, ~s and ~c are padded on the right unless the @ modifier is given, as in ~10@a, while
+// numbers are always padded on the left. With ~d the @ modifier also prints a + sign.
+
+// function: adds format and printf to the given context.
+pub fn register(symbols: &mut LispContext) {
+    symbols.insert_func("format", format);
+    symbols.insert_func("printf", printf);
+}
+
+// struct: a directive read from a format string.
+// params: the comma separated parameters before the directive, None where left empty.
+// at: whether the @ modifier was given.
+// kind: the character selecting the directive, in lower case.
+struct Directive {
+    params: Vec<Option<Param>>,
+    at: bool,
+    kind: char
+}
+
+// enum: a parameter of a directive, either a number or a quoted character such as '0.
+enum Param {
+    Num(usize),
+    Char(char)
+}
+
+impl Directive {
+    fn num(&self, idx: usize) -> Result<Option<usize>, LispError> {
+        match self.params.get(idx) {
+            Some(Some(Param::Num(n))) => Ok(Some(*n)),
+            Some(Some(Param::Char(_))) => Err(error(format!("expected a number for parameter {} of ~{}", idx + 1, self.kind))),
+            _ => Ok(None)
+        }
+    }
+
+    fn pad_char(&self, idx: usize) -> Result<char, LispError> {
+        match self.params.get(idx) {
+            Some(Some(Param::Char(ch))) => Ok(*ch),
+            Some(Some(Param::Num(_))) => Err(error(format!("expected a character for parameter {} of ~{}", idx + 1, self.kind))),
+            _ => Ok(' ')
+        }
+    }
+}
+
+fn error(msg: String) -> LispError {
+    LispError::EvalError(format!("format: {}.", msg))
+}
+
+// the largest width or number of digits a directive may ask for.
+const MAX_PARAM: usize = 65535;
+
+// function: reads the parameters, modifiers and kind of the directive following a ~.
+fn directive(chars: &[char], idx: &mut usize) -> Result<Directive, LispError> {
+    let mut params = Vec::new();
+    let mut param = None;
+    let mut at = false;
+
+    loop {
+        let ch = match chars.get(*idx) {
+            Some(ch) => *ch,
+            None => return Err(error("format string ends inside a directive".to_string()))
+        };
+        *idx += 1;
+
+        match ch {
+            '0'..='9' if !at => {
+                let digit = ch.to_digit(10).unwrap() as usize;
+                param = match param {
+                    Some(Param::Num(n)) => match n.checked_mul(10).and_then(|n| n.checked_add(digit)) {
+                        Some(n) if n <= MAX_PARAM => Some(Param::Num(n)),
+                        _ => return Err(error(format!("directive parameter larger than {}", MAX_PARAM)))
+                    },
+                    Some(Param::Char(_)) => return Err(error("malformed directive parameter".to_string())),
+                    None => Some(Param::Num(digit))
+                };
+            },
+            '\'' if !at && param.is_none() => match chars.get(*idx) {
+                Some(c) => {
+                    param = Some(Param::Char(*c));
+                    *idx += 1;
+                },
+                None => return Err(error("format string ends inside a directive".to_string()))
+            },
+            ',' if !at => params.push(param.take()),
+            '@' => at = true,
+            _ => {
+                if param.is_some() || !params.is_empty() {
+                    params.push(param.take());
+                }
+
+                return Ok(Directive { params, at, kind: ch.to_ascii_lowercase() });
+            }
+        }
+    }
+}
+
+// function: pads text with the fill character to the given width, on the left or the right.
+fn pad(text: String, width: Option<usize>, fill: char, left: bool) -> String {
+    let len = text.chars().count();
+
+    match width {
+        Some(width) if width > len => {
+            let padding : String = std::iter::repeat_n(fill, width - len).collect();
+
+            if left {
+                padding + &text
+            } else {
+                text + &padding
+            }
+        },
+        _ => text
+    }
+}
+
+// function: converts a number argument to an integer for the integer directives.
+fn to_integer(arg: &LispToken, kind: char) -> Result<i64, LispError> {
+    let f = arg.to_float()?;
+
+    if f.fract() != 0.0 || !f.is_finite() {
+        return Err(error(format!("~{} expects an integer, got {}", kind, arg)));
+    }
+
+    Ok(f as i64)
+}
+
+// function: formats an integer in the given radix, with a + sign for positive numbers when requested.
+fn radix(n: i64, base: u32, sign: bool) -> String {
+    let digits = match base {
+        16 => format!("{:x}", n.unsigned_abs()),
+        8 => format!("{:o}", n.unsigned_abs()),
+        2 => format!("{:b}", n.unsigned_abs()),
+        _ => format!("{}", n.unsigned_abs())
+    };
+
+    if n < 0 {
+        format!("-{}", digits)
+    } else if sign {
+        format!("+{}", digits)
+    } else {
+        digits
+    }
+}
+
+// function: replaces the directives in a format string with the formatted arguments.
+fn format_str(fmt: &str, args: &[LispToken]) -> Result<String, LispError> {
+    let chars : Vec<char> = fmt.chars().collect();
+    let mut args = args.iter();
+    let mut out = String::new();
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        let ch = chars[idx];
+        idx += 1;
+
+        if ch != '~' {
+            out.push(ch);
+            continue;
+        }
+
+        let d = directive(&chars, &mut idx)?;
+
+        let text = match d.kind {
+            '%' => "\n".repeat(d.num(0)?.unwrap_or(1)),
+            '~' => "~".repeat(d.num(0)?.unwrap_or(1)),
+            _ => {
+                let arg = match args.next() {
+                    Some(arg) => arg,
+                    None => return Err(error("not enough arguments".to_string()))
+                };
+
+                match d.kind {
+                    'a' => pad(format!("{}", arg.printed(PrintMode::Display)), d.num(0)?, d.pad_char(1)?, d.at),
+                    's' => pad(format!("{}", arg.printed(PrintMode::Write)), d.num(0)?, d.pad_char(1)?, d.at),
+                    'c' => pad(arg.to_char()?.to_string(), d.num(0)?, d.pad_char(1)?, d.at),
+                    'd' => pad(radix(to_integer(arg, 'd')?, 10, d.at), d.num(0)?, d.pad_char(1)?, true),
+                    'x' => pad(radix(to_integer(arg, 'x')?, 16, d.at), d.num(0)?, d.pad_char(1)?, true),
+                    'o' => pad(radix(to_integer(arg, 'o')?, 8, d.at), d.num(0)?, d.pad_char(1)?, true),
+                    'b' => pad(radix(to_integer(arg, 'b')?, 2, d.at), d.num(0)?, d.pad_char(1)?, true),
+                    'f' => {
+                        let value = arg.to_float()?;
+                        let text = match d.num(1)? {
+                            Some(digits) => format!("{:.*}", digits, value),
+                            None => format!("{}", value)
+                        };
+
+                        pad(text, d.num(0)?, d.pad_char(2)?, true)
+                    },
+                    kind => return Err(error(format!("unknown directive `~{}`", kind)))
+                }
+            }
+        };
+
+        out.push_str(&text);
+    }
+
+    if args.next().is_some() {
+        return Err(error("too many arguments".to_string()));
+    }
+
+    Ok(out)
+}
+
+// function: evaluates the format string and its arguments and returns the formatted text.
+fn eval_format(ctx: &mut LispContext, args: &[LispToken]) -> Result<String, LispError> {
+    let xs = eval_vec(ctx, args)?;
+
+    match xs.split_first() {
+        Some((fmt, rest)) => format_str(&fmt.to_str()?, rest),
+        None => Err(LispError::InvalidNoArguments)
+    }
+}
+
+// function: returns the formatted text as a string.
+fn format(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    Ok(LispToken::Str(eval_format(ctx, args)?))
+}
+
+// function: writes the formatted text to the current output port.
+fn printf(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let text = eval_format(ctx, args)?;
+    write_to(ctx, None, &text)?;
+    Ok(LispToken::nil())
+}
@@ -0,0 +1,305 @@
+use crate::context::{LispContext};
+use crate::eval::{apply_proc, eval, eval_args, LispResult};
+use crate::records::{LispRecordType};
+use crate::tokens::{LispError, LispToken};
+
+use itertools::{Itertools};
+use std::cell::{RefCell};
+use std::rc::{Rc};
+
+// (defgeneric describe (x)) defines a generic function, and
+// (defmethod describe ((p point) other) body...) adds a method to it, specialized on the types
+// of its parameters. A parameter without a type accepts any value, like the type t.
+//
+// Every value has a list of types, from the most to the least specific:
+//   records      the record type, record, t
+//   ()           null, list, t
+//   lambdas      procedure, pair, list, t
+//   other lists  pair, list, t
+//   dotted lists pair, t
+//   #t and #f    boolean, symbol, t
+//   generics     generic, procedure, t
+//   builtins     procedure, t
+// and for all other values their own type followed by t, such as number, string, char, symbol,
+// vector, hash-table, bytevector, promise, port, regex, record-type or values. Record types are
+// named by the symbol they are bound to, such as point or <point>, and other types by their
+// name, optionally in angle brackets.
+//
+// A call runs the most specific of the methods whose types match all of the arguments. Two
+// methods are ordered by comparing their types argument by argument from the left: at the
+// first argument where they differ, the method whose type comes earlier in the type list of
+// the argument is more specific. Within a method, (call-next-method) calls the next most
+// specific method with the same arguments, or with new arguments if any are given.
+
+// enum: a type that methods are specialized on.
+#[derive(Clone)]
+pub enum LispType {
+    Builtin(&'static str),
+    Record(Rc<LispRecordType>)
+}
+
+impl LispType {
+    fn name(&self) -> &str {
+        match self {
+            LispType::Builtin(name) => name,
+            LispType::Record(rtype) => &rtype.name
+        }
+    }
+
+    fn is(&self, other: &LispType) -> bool {
+        match (self, other) {
+            (LispType::Builtin(a), LispType::Builtin(b)) => a == b,
+            (LispType::Record(a), LispType::Record(b)) => Rc::ptr_eq(a, b),
+            _ => false
+        }
+    }
+}
+
+const BUILTIN_TYPES: [&str; 20] = [
+    "boolean", "bytevector", "char", "generic", "hash-table", "list", "null", "number", "pair",
+    "port", "procedure", "promise", "record", "record-type", "regex", "string", "symbol", "t",
+    "values", "vector"
+];
+
+// function: returns the types of a value, from the most to the least specific.
+fn type_list(value: &LispToken) -> Vec<LispType> {
+    let names : &[&'static str] = match value {
+        LispToken::Record(record) => {
+            return vec![LispType::Record(record.rtype.clone()), LispType::Builtin("record"), LispType::Builtin("t")];
+        },
+        LispToken::Bytes(_) => &["bytevector"],
+        LispToken::Char(_) => &["char"],
+        LispToken::DottedList(..) => &["pair"],
+        LispToken::Func(..) => &["procedure"],
+        LispToken::Generic(_) => &["generic", "procedure"],
+        LispToken::Hash(_) => &["hash-table"],
+        LispToken::List(xs) if xs.is_empty() => &["null", "list"],
+        LispToken::List(xs) if xs.len() >= 3 && xs[0].is_sym("lambda") => &["procedure", "pair", "list"],
+        LispToken::List(_) => &["pair", "list"],
+        LispToken::MutStr(_) | LispToken::Str(_) => &["string"],
+        LispToken::Num(_) => &["number"],
+        LispToken::Port(_) => &["port"],
+        LispToken::Promise(_) => &["promise"],
+        LispToken::Quote(_) => &["pair", "list"],
+        LispToken::RecordType(_) => &["record-type"],
+        LispToken::Regex(_) => &["regex"],
+        LispToken::Sym(s) if s == "#t" || s == "#f" => &["boolean", "symbol"],
+        LispToken::Sym(_) => &["symbol"],
+        LispToken::Values(_) => &["values"],
+        LispToken::Vector(_) => &["vector"]
+    };
+
+    names.iter().chain(&["t"]).map(|name| LispType::Builtin(name)).collect()
+}
+
+// struct: a method of a generic function.
+// types: the type each argument must have.
+// lambda: the procedure run for the method.
+struct Method {
+    types: Vec<LispType>,
+    lambda: LispToken
+}
+
+// struct: a generic function.
+// name: the name the function was defined with.
+// arity: the number of arguments every method takes.
+// methods: the methods of the function, in the order they were defined.
+pub struct LispGeneric {
+    pub name: String,
+    arity: usize,
+    methods: Vec<Method>
+}
+
+// function: adds generic functions to the given context.
+pub fn register(symbols: &mut LispContext) {
+    symbols.insert_func("defgeneric", defgeneric);
+    symbols.insert_func("defmethod", defmethod);
+    symbols.insert_func("call-next-method", call_next_method);
+    symbols.insert_func("next-method?", has_next_method);
+    symbols.insert_func("type-of", type_of);
+}
+
+fn new_generic(name: &str, arity: usize) -> LispToken {
+    LispToken::Generic(Rc::new(RefCell::new(LispGeneric { name: name.to_string(), arity, methods: Vec::new() })))
+}
+
+// function: returns the generic function bound to the name, defining it when the name is
+// unbound or bound to something else.
+fn generic_named(ctx: &mut LispContext, name: &str, arity: usize) -> Result<LispToken, LispError> {
+    if let Some(LispToken::Generic(generic)) = ctx.get(name) {
+        if generic.borrow().arity != arity {
+            return Err(LispError::EvalError(format!("{} takes {} arguments.", name, generic.borrow().arity)));
+        }
+
+        return Ok(LispToken::Generic(generic.clone()));
+    }
+
+    let generic = new_generic(name, arity);
+    ctx.insert(name, generic.clone());
+    Ok(generic)
+}
+
+// function: resolves a type name used in a method's parameter list.
+fn to_type(ctx: &mut LispContext, token: &LispToken) -> Result<LispType, LispError> {
+    if let LispToken::Sym(s) = token {
+        let name = s.strip_prefix('<').and_then(|n| n.strip_suffix('>')).unwrap_or(s);
+
+        if let Some(name) = BUILTIN_TYPES.iter().find(|n| **n == name) {
+            return Ok(LispType::Builtin(name));
+        }
+    }
+
+    match eval(ctx, token) {
+        Ok(LispToken::RecordType(rtype)) => Ok(LispType::Record(rtype)),
+        Err(err @ LispError::Escape(..)) => Err(err),
+        _ => Err(LispError::EvalError(format!("`{}` is not a type.", token)))
+    }
+}
+
+// function: (defgeneric name (params...)) defines a generic function taking the given
+// number of arguments, keeping the methods of an existing one.
+fn defgeneric(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() != 2 {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    match (&args[0], &args[1]) {
+        (LispToken::Sym(name), LispToken::List(params)) => generic_named(ctx, name, params.len()),
+        _ => Err(LispError::InvalidArguments)
+    }
+}
+
+// function: (defmethod name (params...) body...) adds a method to a generic function,
+// replacing any method with the same types. each parameter is either a symbol or a list
+// (symbol type).
+fn defmethod(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() < 3 {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    let (name, params) = match (&args[0], &args[1]) {
+        (LispToken::Sym(name), LispToken::List(params)) => (name, params),
+        _ => return Err(LispError::InvalidArguments)
+    };
+
+    let mut names = Vec::new();
+    let mut types = Vec::new();
+
+    for param in params {
+        match param {
+            LispToken::Sym(_) => {
+                names.push(param.clone());
+                types.push(LispType::Builtin("t"));
+            },
+            LispToken::List(spec) if spec.len() == 2 && matches!(spec[0], LispToken::Sym(_)) => {
+                names.push(spec[0].clone());
+                types.push(to_type(ctx, &spec[1])?);
+            },
+            _ => return Err(LispError::EvalError(format!("invalid method parameter `{}`.", param)))
+        }
+    }
+
+    let mut lambda = vec![LispToken::Sym("lambda".to_string()), LispToken::List(names)];
+    lambda.extend_from_slice(&args[2..]);
+
+    let generic = generic_named(ctx, name, params.len())?;
+
+    if let LispToken::Generic(g) = &generic {
+        let mut g = g.borrow_mut();
+        let method = Method { types, lambda: LispToken::List(lambda) };
+
+        match g.methods.iter().position(|m| m.types.iter().zip(&method.types).all(|(a, b)| a.is(b))) {
+            Some(idx) => g.methods[idx] = method,
+            None => g.methods.push(method)
+        }
+    }
+
+    // memoized calls of the generic function may have run a different method.
+    ctx.invalidate();
+    Ok(generic)
+}
+
+// function: returns the procedures of the methods applicable to the arguments, from the most
+// to the least specific.
+fn applicable_methods(generic: &LispGeneric, args: &[LispToken]) -> Vec<LispToken> {
+    let type_lists : Vec<Vec<LispType>> = args.iter().map(type_list).collect();
+
+    let mut ranked : Vec<(Vec<usize>, &Method)> = generic.methods.iter().filter_map(|method| {
+        let ranks = method.types.iter().zip(&type_lists)
+            .map(|(t, types)| types.iter().position(|u| u.is(t)))
+            .collect::<Option<Vec<usize>>>()?;
+
+        Some((ranks, method))
+    }).collect();
+
+    ranked.sort_by(|a, b| a.0.cmp(&b.0));
+    ranked.into_iter().map(|(_, method)| method.lambda.clone()).collect()
+}
+
+// function: runs the first of the methods, making the others available to call-next-method.
+fn run_methods(ctx: &mut LispContext, mut methods: Vec<LispToken>, args: Vec<LispToken>) -> LispResult {
+    let method = methods.remove(0);
+
+    ctx.push_next_methods(methods, args.clone());
+    let result = apply_proc(ctx, &method, args);
+    ctx.pop_next_methods();
+
+    result
+}
+
+// function: calls a generic function, running the most specific applicable method.
+pub(crate) fn apply_generic(ctx: &mut LispContext, generic: &Rc<RefCell<LispGeneric>>, args: Vec<LispToken>) -> LispResult {
+    let methods = {
+        let generic = generic.borrow();
+
+        if args.len() != generic.arity {
+            return Err(LispError::InvalidNoArguments);
+        }
+
+        let methods = applicable_methods(&generic, &args);
+
+        if methods.is_empty() {
+            let types = args.iter().map(|arg| type_list(arg)[0].name().to_string()).join(" ");
+            return Err(LispError::EvalError(format!("no method of {} applies to arguments of types ({}).", generic.name, types)));
+        }
+
+        methods
+    };
+
+    run_methods(ctx, methods, args)
+}
+
+// function: calls the next most specific method, with the arguments of the current method
+// unless new ones are given.
+fn call_next_method(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 0, usize::MAX)?;
+
+    // the result depends on the method being run, which isn't part of the expression.
+    ctx.record_effect();
+
+    let (methods, current) = match ctx.next_methods() {
+        Some((methods, current)) => (methods.to_vec(), current.to_vec()),
+        None => return Err(LispError::EvalError("call-next-method used outside of a method.".to_string()))
+    };
+
+    if methods.is_empty() {
+        return Err(LispError::EvalError("no next method.".to_string()));
+    }
+
+    run_methods(ctx, methods, if xs.is_empty() { current } else { xs })
+}
+
+// function: checks whether the current method has a next method to call.
+fn has_next_method(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    eval_args(ctx, args, 0, 0)?;
+    ctx.record_effect();
+
+    let found = ctx.next_methods().is_some_and(|(methods, _)| !methods.is_empty());
+    Ok(LispToken::from(found))
+}
+
+// function: returns the name of the most specific type of a value.
+fn type_of(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(LispToken::Sym(type_list(&xs[0])[0].name().to_string()))
+}
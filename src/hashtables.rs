@@ -0,0 +1,299 @@
+use crate::context::{LispContext};
+use crate::eval::{eval_args, LispResult};
+use crate::tokens::{LispError, LispToken};
+
+use std::cell::{RefCell};
+use std::collections::hash_map::{DefaultHasher};
+use std::collections::{HashMap};
+use std::hash::{Hash, Hasher};
+use std::rc::{Rc};
+
+// Hash tables map keys to values using one of the equality tests eq?, eqv? or equal?, with
+// equal? being the default. They are written as {key value ...}, where neither the keys nor
+// the values are evaluated when read, and such literals always compare keys with equal?.
+//
+// Keys compared with equal? must not be changed in place while they are in a table.
+
+// enum: the equality test used to compare the keys of a hash table.
+#[derive(Clone, Copy, PartialEq)]
+pub enum HashTest {
+    Eq,
+    Eqv,
+    Equal
+}
+
+impl HashTest {
+    fn equal(self, a: &LispToken, b: &LispToken) -> bool {
+        match self {
+            HashTest::Eq => a.is_eq(b),
+            HashTest::Eqv => a.is_eqv(b),
+            HashTest::Equal => a.is_equal(b)
+        }
+    }
+}
+
+// struct: a hash table keeping its entries in insertion order, except that removing an entry
+// moves the last one into its place.
+// test: the equality test used to compare keys.
+// entries: the key value pairs of the table.
+// index: the positions of the entries, grouped by the hash of their keys.
+pub struct LispHash {
+    pub test: HashTest,
+    entries: Vec<(LispToken, LispToken)>,
+    index: HashMap<u64, Vec<usize>>
+}
+
+impl LispHash {
+    pub fn new(test: HashTest) -> LispHash {
+        LispHash {
+            test,
+            entries: Vec::new(),
+            index: HashMap::new()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn entries(&self) -> &[(LispToken, LispToken)] {
+        &self.entries
+    }
+
+    fn hash(&self, key: &LispToken) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hash_token(key, self.test, &mut hasher);
+        hasher.finish()
+    }
+
+    fn position(&self, key: &LispToken) -> Option<usize> {
+        let positions = self.index.get(&self.hash(key))?;
+        positions.iter().copied().find(|&pos| self.test.equal(&self.entries[pos].0, key))
+    }
+
+    pub fn get(&self, key: &LispToken) -> Option<&LispToken> {
+        self.position(key).map(|pos| &self.entries[pos].1)
+    }
+
+    // function: sets the value of a key, replacing any previous value.
+    pub fn insert(&mut self, key: LispToken, value: LispToken) {
+        if let Some(pos) = self.position(&key) {
+            self.entries[pos].1 = value;
+            return;
+        }
+
+        let hash = self.hash(&key);
+        self.index.entry(hash).or_default().push(self.entries.len());
+        self.entries.push((key, value));
+    }
+
+    // function: removes a key, returning its value. the last entry takes the place of the removed one.
+    pub fn remove(&mut self, key: &LispToken) -> Option<LispToken> {
+        let pos = self.position(key)?;
+        self.unindex(self.hash(key), pos);
+
+        let last = self.entries.len() - 1;
+        if pos != last {
+            let hash = self.hash(&self.entries[last].0);
+            self.unindex(hash, last);
+            self.index.entry(hash).or_default().push(pos);
+        }
+
+        Some(self.entries.swap_remove(pos).1)
+    }
+
+    fn unindex(&mut self, hash: u64, pos: usize) {
+        if let Some(positions) = self.index.get_mut(&hash) {
+            positions.retain(|&p| p != pos);
+
+            if positions.is_empty() {
+                self.index.remove(&hash);
+            }
+        }
+    }
+}
+
+// function: hashes a token consistently with the equality test, so that keys which are
+// equal under the test always have the same hash.
+fn hash_token<H: Hasher>(token: &LispToken, test: HashTest, state: &mut H) {
+    match token {
+        LispToken::Char(ch) => ch.hash(state),
+        LispToken::Func(_, f) => (*f as usize).hash(state),
+        LispToken::Generic(x) => (Rc::as_ptr(x) as usize).hash(state),
+        // eq? compares the items of lists with eqv?.
+        LispToken::List(xs) => {
+            let test = if test == HashTest::Eq { HashTest::Eqv } else { test };
+
+            xs.len().hash(state);
+            for x in xs {
+                hash_token(x, test, state);
+            }
+        },
+        LispToken::DottedList(xs, last) => {
+            let test = if test == HashTest::Eq { HashTest::Eqv } else { test };
+
+            xs.len().hash(state);
+            for x in xs {
+                hash_token(x, test, state);
+            }
+            hash_token(last, test, state);
+        },
+        LispToken::Num(n) if test == HashTest::Eq => n.hash(state),
+        LispToken::Num(n) => match n.parse::<f64>() {
+            // 0.0 and -0.0 are eqv?, so both hash as 0.0.
+            Ok(x) => (if x == 0.0 { 0.0 } else { x }).to_bits().hash(state),
+            Err(_) => n.hash(state)
+        },
+        LispToken::Quote(x) => hash_token(x, test, state),
+        LispToken::Str(s) | LispToken::Sym(s) => s.hash(state),
+        LispToken::Bytes(bytes) if test == HashTest::Equal => bytes.borrow().hash(state),
+        LispToken::MutStr(s) if test == HashTest::Equal => s.borrow().hash(state),
+        LispToken::Regex(re) if test == HashTest::Equal => re.pattern.hash(state),
+        LispToken::Values(xs) => {
+            xs.len().hash(state);
+            for x in xs {
+                hash_token(x, test, state);
+            }
+        },
+        LispToken::Vector(xs) if test == HashTest::Equal => {
+            let xs = xs.borrow();
+
+            xs.len().hash(state);
+            for x in xs.iter() {
+                hash_token(x, test, state);
+            }
+        },
+        // tables equal? to each other may hold their entries in any order.
+        LispToken::Hash(table) if test == HashTest::Equal => table.borrow().len().hash(state),
+        LispToken::Bytes(x) => (Rc::as_ptr(x) as usize).hash(state),
+        LispToken::Hash(x) => (Rc::as_ptr(x) as usize).hash(state),
+        LispToken::MutStr(x) => (Rc::as_ptr(x) as usize).hash(state),
+        LispToken::Port(x) => (Rc::as_ptr(x) as usize).hash(state),
+        LispToken::Promise(x) => (Rc::as_ptr(x) as usize).hash(state),
+        LispToken::Record(x) => (Rc::as_ptr(x) as usize).hash(state),
+        LispToken::RecordType(x) => (Rc::as_ptr(x) as usize).hash(state),
+        LispToken::Regex(x) => (Rc::as_ptr(x) as usize).hash(state),
+        LispToken::Vector(x) => (Rc::as_ptr(x) as usize).hash(state)
+    }
+}
+
+// function: adds the hash table functions to the given context.
+pub fn register(symbols: &mut LispContext) {
+    symbols.insert_func("make-hash-table", make_hash_table);
+    symbols.insert_func("hash-table?", is_hash_table);
+    symbols.insert_func("hash-ref", hash_ref);
+    symbols.insert_func("hash-set!", hash_set);
+    symbols.insert_func("hash-remove!", hash_remove);
+    symbols.insert_func("hash-contains?", hash_contains);
+    symbols.insert_func("hash-keys", hash_keys);
+    symbols.insert_func("hash-values", hash_values);
+    symbols.insert_func("hash-count", hash_count);
+    symbols.insert_func("hash->alist", hash_to_alist);
+}
+
+fn to_hash(token: &LispToken) -> Result<&Rc<RefCell<LispHash>>, LispError> {
+    match token {
+        LispToken::Hash(table) => Ok(table),
+        _ => Err(LispError::EvalError("value is not a hash table.".to_string()))
+    }
+}
+
+// function: reads the equality test of a hash table, given either as one of the procedures
+// eq?, eqv? and equal? or as their name.
+fn to_test(token: &LispToken) -> Result<HashTest, LispError> {
+    let name = match token {
+        LispToken::Func(name, _) => *name,
+        LispToken::Sym(name) => name,
+        _ => ""
+    };
+
+    match name {
+        "eq?" | "eq" => Ok(HashTest::Eq),
+        "eqv?" => Ok(HashTest::Eqv),
+        "equal?" => Ok(HashTest::Equal),
+        _ => Err(LispError::EvalError(format!("unsupported hash table test `{}`.", token)))
+    }
+}
+
+// function: returns a new, empty hash table comparing keys with the given test or equal?.
+fn make_hash_table(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 0, 1)?;
+
+    let test = match xs.first() {
+        Some(test) => to_test(test)?,
+        None => HashTest::Equal
+    };
+
+    // every call creates a distinct table, so the result must not be memoized.
+    ctx.record_effect();
+    Ok(LispToken::Hash(Rc::new(RefCell::new(LispHash::new(test)))))
+}
+
+fn is_hash_table(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(LispToken::from(to_hash(&xs[0]).is_ok()))
+}
+
+// function: returns the value of a key, or the default if the key is missing.
+fn hash_ref(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 3)?;
+    let table = to_hash(&xs[0])?.borrow();
+
+    match (table.get(&xs[1]), xs.get(2)) {
+        (Some(value), _) => Ok(value.clone()),
+        (None, Some(default)) => Ok(default.clone()),
+        (None, None) => Err(LispError::EvalError(format!("key `{}` not found in hash table.", xs[1])))
+    }
+}
+
+fn hash_set(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 3, 3)?;
+    to_hash(&xs[0])?.borrow_mut().insert(xs[1].clone(), xs[2].clone());
+
+    // memoized results may have read the old contents.
+    ctx.invalidate();
+    Ok(LispToken::nil())
+}
+
+fn hash_remove(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    to_hash(&xs[0])?.borrow_mut().remove(&xs[1]);
+
+    // memoized results may have read the old contents.
+    ctx.invalidate();
+    Ok(LispToken::nil())
+}
+
+fn hash_contains(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let found = to_hash(&xs[0])?.borrow().get(&xs[1]).is_some();
+    Ok(LispToken::from(found))
+}
+
+fn hash_keys(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    let keys = to_hash(&xs[0])?.borrow().entries().iter().map(|(k, _)| k.clone()).collect();
+    Ok(LispToken::List(keys))
+}
+
+fn hash_values(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    let values = to_hash(&xs[0])?.borrow().entries().iter().map(|(_, v)| v.clone()).collect();
+    Ok(LispToken::List(values))
+}
+
+fn hash_count(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    let len = to_hash(&xs[0])?.borrow().len();
+    Ok(LispToken::from(len as f64))
+}
+
+// function: returns the entries of a hash table as an association list of (key . value) pairs.
+fn hash_to_alist(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    let entries = to_hash(&xs[0])?.borrow().entries().iter()
+        .map(|(k, v)| LispToken::cons(k.clone(), v.clone()))
+        .collect();
+
+    Ok(LispToken::List(entries))
+}
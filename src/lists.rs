@@ -0,0 +1,360 @@
+use crate::context::{LispContext};
+use crate::eval::{apply_proc, eval_args, eval_vec, LispResult};
+use crate::tokens::{LispError, LispToken};
+use crate::vectors::{check_index};
+
+// The list library follows the naming of SRFI-1. Every function loops in Rust rather than
+// recursing, so the length of a list is only limited by memory. Functions taking several
+// lists, such as map, stop at the end of the shortest one.
+
+// function: adds the list library to the given context.
+pub fn register(symbols: &mut LispContext) {
+    symbols.insert_func("list", list);
+    symbols.insert_func("length", length);
+    symbols.insert_func("append", append);
+    symbols.insert_func("reverse", reverse);
+    symbols.insert_func("list-ref", list_ref);
+    symbols.insert_func("list-tail", list_tail);
+    symbols.insert_func("last", last);
+    symbols.insert_func("iota", iota);
+    symbols.insert_func("flatten", flatten);
+    symbols.insert_func("map", map);
+    symbols.insert_func("for-each", for_each);
+    symbols.insert_func("filter", filter);
+    symbols.insert_func("remove", remove);
+    symbols.insert_func("fold", fold);
+    symbols.insert_func("fold-left", fold_left);
+    symbols.insert_func("fold-right", fold_right);
+    symbols.insert_func("reduce", reduce);
+    symbols.insert_func("member", member);
+    symbols.insert_func("memq", memq);
+    symbols.insert_func("memv", memv);
+    symbols.insert_func("any", any);
+    symbols.insert_func("every", every);
+}
+
+// function: returns the items of a proper list.
+pub(crate) fn to_list(token: &LispToken) -> Result<&[LispToken], LispError> {
+    match token {
+        LispToken::List(xs) => Ok(xs),
+        _ => Err(LispError::EvalError("value is not a list.".to_string()))
+    }
+}
+
+// function: returns the argument lists of each call made by a procedure mapped over several
+// lists, stopping at the end of the shortest one.
+fn transpose(lists: &[LispToken]) -> Result<Vec<Vec<LispToken>>, LispError> {
+    let lists = lists.iter().map(to_list).collect::<Result<Vec<_>, LispError>>()?;
+    let len = lists.iter().map(|xs| xs.len()).min().unwrap_or(0);
+
+    Ok((0..len).map(|idx| lists.iter().map(|xs| xs[idx].clone()).collect()).collect())
+}
+
+// function: evaluates a procedure followed by at least one list.
+fn eval_proc_lists(ctx: &mut LispContext, args: &[LispToken]) -> Result<(LispToken, Vec<Vec<LispToken>>), LispError> {
+    let xs = eval_vec(ctx, args)?;
+
+    if xs.len() < 2 {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    Ok((xs[0].clone(), transpose(&xs[1..])?))
+}
+
+// function: returns a list of the arguments.
+fn list(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    Ok(LispToken::List(eval_vec(ctx, args)?))
+}
+
+fn length(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(LispToken::from(to_list(&xs[0])?.len() as f64))
+}
+
+// function: joins lists. the last argument may be any value, which becomes the tail of the result.
+fn append(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let mut xs = eval_vec(ctx, args)?;
+
+    let tail = match xs.pop() {
+        Some(last) => last,
+        None => return Ok(LispToken::nil())
+    };
+
+    let mut items = Vec::new();
+    for lst in &xs {
+        items.extend_from_slice(to_list(lst)?);
+    }
+
+    match tail {
+        LispToken::List(rest) => {
+            items.extend(rest);
+            Ok(LispToken::List(items))
+        },
+        LispToken::DottedList(rest, last) => {
+            items.extend(rest);
+            Ok(LispToken::DottedList(items, last))
+        },
+        tail if items.is_empty() => Ok(tail),
+        tail => Ok(LispToken::DottedList(items, Box::new(tail)))
+    }
+}
+
+fn reverse(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(LispToken::List(to_list(&xs[0])?.iter().rev().cloned().collect()))
+}
+
+fn list_ref(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let lst = to_list(&xs[0])?;
+    let idx = check_index(xs[1].to_index()?, lst.len())?;
+    Ok(lst[idx].clone())
+}
+
+// function: returns the list without its first k items.
+fn list_tail(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let lst = to_list(&xs[0])?;
+    let k = xs[1].to_index()?;
+
+    if k > lst.len() {
+        return Err(LispError::EvalError(format!("index {} out of range for length {}.", k, lst.len())));
+    }
+
+    Ok(LispToken::List(lst[k..].to_vec()))
+}
+
+fn last(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+
+    match to_list(&xs[0])?.last() {
+        Some(x) => Ok(x.clone()),
+        None => Err(LispError::EvalError("last of an empty list.".to_string()))
+    }
+}
+
+// function: returns a list of count numbers, counting from start, or 0, in steps of step, or 1.
+fn iota(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 3)?;
+    let count = xs[0].to_index()?;
+
+    let start = match xs.get(1) {
+        Some(start) => start.to_float()?,
+        None => 0.0
+    };
+
+    let step = match xs.get(2) {
+        Some(step) => step.to_float()?,
+        None => 1.0
+    };
+
+    let mut result = Vec::new();
+    if result.try_reserve(count).is_err() {
+        return Err(LispError::EvalError(format!("cannot make a list of {} numbers.", xs[0])));
+    }
+
+    result.extend((0..count).map(|i| LispToken::from(start + i as f64 * step)));
+    Ok(LispToken::List(result))
+}
+
+// function: returns the atoms of a nested list, in order, as a single flat list.
+fn flatten(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    let mut result = Vec::new();
+    let mut stack = vec![xs[0].clone()];
+
+    while let Some(x) = stack.pop() {
+        match x {
+            LispToken::List(items) => stack.extend(items.into_iter().rev()),
+            LispToken::DottedList(items, last) => {
+                stack.push(*last);
+                stack.extend(items.into_iter().rev());
+            },
+            atom => result.push(atom)
+        }
+    }
+
+    Ok(LispToken::List(result))
+}
+
+// function: returns the results of calling the procedure on the items of the lists.
+fn map(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let (proc, calls) = eval_proc_lists(ctx, args)?;
+    let mut result = Vec::new();
+
+    for call in calls {
+        result.push(apply_proc(ctx, &proc, call)?);
+    }
+
+    Ok(LispToken::List(result))
+}
+
+// function: calls the procedure on the items of the lists for its side effects.
+fn for_each(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let (proc, calls) = eval_proc_lists(ctx, args)?;
+
+    for call in calls {
+        apply_proc(ctx, &proc, call)?;
+    }
+
+    Ok(LispToken::nil())
+}
+
+// function: returns the items for which the predicate returns the given truth value.
+fn partition(ctx: &mut LispContext, args: &[LispToken], keep: bool) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let mut result = Vec::new();
+
+    for x in to_list(&xs[1])? {
+        let test = apply_proc(ctx, &xs[0], vec![x.clone()])?;
+
+        if ctx.is_true(&test) == keep {
+            result.push(x.clone());
+        }
+    }
+
+    Ok(LispToken::List(result))
+}
+
+fn filter(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    partition(ctx, args, true)
+}
+
+fn remove(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    partition(ctx, args, false)
+}
+
+// function: evaluates the arguments of a fold, a procedure and an initial value followed by
+// at least one list.
+fn eval_fold_args(ctx: &mut LispContext, args: &[LispToken]) -> Result<(LispToken, LispToken, Vec<Vec<LispToken>>), LispError> {
+    let xs = eval_vec(ctx, args)?;
+
+    if xs.len() < 3 {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    Ok((xs[0].clone(), xs[1].clone(), transpose(&xs[2..])?))
+}
+
+// function: SRFI-1 fold, calling (proc item ... acc) from the left.
+fn fold(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let (proc, mut acc, calls) = eval_fold_args(ctx, args)?;
+
+    for mut call in calls {
+        call.push(acc);
+        acc = apply_proc(ctx, &proc, call)?;
+    }
+
+    Ok(acc)
+}
+
+// function: calls (proc acc item ...) from the left, so (fold-left - 0 '(1 2)) is (- (- 0 1) 2).
+fn fold_left(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let (proc, mut acc, calls) = eval_fold_args(ctx, args)?;
+
+    for mut call in calls {
+        call.insert(0, acc);
+        acc = apply_proc(ctx, &proc, call)?;
+    }
+
+    Ok(acc)
+}
+
+// function: calls (proc item ... acc) from the right, so (fold-right - 0 '(1 2)) is (- 1 (- 2 0)).
+fn fold_right(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let (proc, mut acc, calls) = eval_fold_args(ctx, args)?;
+
+    for mut call in calls.into_iter().rev() {
+        call.push(acc);
+        acc = apply_proc(ctx, &proc, call)?;
+    }
+
+    Ok(acc)
+}
+
+// function: folds the list with its first item as the initial value, returning the identity
+// when the list is empty.
+fn reduce(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 3, 3)?;
+    let lst = to_list(&xs[2])?;
+
+    let mut acc = match lst.first() {
+        Some(first) => first.clone(),
+        None => return Ok(xs[1].clone())
+    };
+
+    for x in &lst[1..] {
+        acc = apply_proc(ctx, &xs[0], vec![x.clone(), acc])?;
+    }
+
+    Ok(acc)
+}
+
+// function: returns the tail of the list starting at the first item matching the value, or #f.
+fn find_tail(ctx: &mut LispContext, x: &LispToken, lst: &LispToken, proc: Option<&LispToken>, test: fn(&LispToken, &LispToken) -> bool) -> LispResult {
+    let lst = to_list(lst)?;
+
+    for (idx, item) in lst.iter().enumerate() {
+        let found = match proc {
+            Some(proc) => {
+                let result = apply_proc(ctx, proc, vec![x.clone(), item.clone()])?;
+                ctx.is_true(&result)
+            },
+            None => test(x, item)
+        };
+
+        if found {
+            return Ok(LispToken::List(lst[idx..].to_vec()));
+        }
+    }
+
+    Ok(LispToken::from(false))
+}
+
+// function: compares with equal?, or with the procedure given as the third argument.
+fn member(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 3)?;
+    find_tail(ctx, &xs[0], &xs[1], xs.get(2), LispToken::is_equal)
+}
+
+fn memq(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    find_tail(ctx, &xs[0], &xs[1], None, LispToken::is_eq)
+}
+
+fn memv(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    find_tail(ctx, &xs[0], &xs[1], None, LispToken::is_eqv)
+}
+
+// function: returns the first true result of calling the predicate on the items of the lists, or #f.
+fn any(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let (proc, calls) = eval_proc_lists(ctx, args)?;
+
+    for call in calls {
+        let result = apply_proc(ctx, &proc, call)?;
+
+        if ctx.is_true(&result) {
+            return Ok(result);
+        }
+    }
+
+    Ok(LispToken::from(false))
+}
+
+// function: returns #f if the predicate is false for any items of the lists, and otherwise
+// the result of the last call, or #t for empty lists.
+fn every(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let (proc, calls) = eval_proc_lists(ctx, args)?;
+    let mut result = LispToken::from(true);
+
+    for call in calls {
+        result = apply_proc(ctx, &proc, call)?;
+
+        if !ctx.is_true(&result) {
+            return Ok(LispToken::from(false));
+        }
+    }
+
+    Ok(result)
+}
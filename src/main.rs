@@ -1,10 +1,27 @@
 extern crate itertools;
+extern crate regex;
 extern crate rustyline;
 
 mod context;
 mod tokens;
 mod parser;
 mod eval;
+mod alists;
+mod bytevectors;
+mod continuations;
+mod format;
+mod generics;
+mod hashtables;
+mod lists;
+mod matching;
+mod ports;
+mod promises;
+mod records;
+mod regexp;
+mod sorting;
+mod strings;
+mod values;
+mod vectors;
 
 use eval::{LispEnv};
 
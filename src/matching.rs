@@ -0,0 +1,299 @@
+use crate::context::{LispContext};
+use crate::eval::{apply_proc, eval, substitute, LispResult};
+use crate::tokens::{LispError, LispToken};
+
+use std::collections::{HashMap};
+use std::rc::{Rc};
+
+// (match expr (pattern body...) ...) evaluates expr and runs the body of the first clause whose
+// pattern matches the value, with the variables of the pattern bound to the matching parts.
+//
+//   _                  matches anything
+//   x                  matches anything and binds it to x. a variable used twice must match
+//                      equal? values both times
+//   1 "s" #\c #t 'x    match values that are equal? to the literal
+//   #nil               matches the empty list
+//   (p ...)            matches a list whose items match the patterns
+//   (p . q)            matches a pair whose car matches p and whose cdr matches q
+//   #(p ...)           matches a vector whose items match the patterns
+//   ($ type p ...)     matches a record of the type whose first fields match the patterns
+//   (? pred p ...)     matches a value for which (pred value) is true and which matches the patterns
+//
+// In a list or vector pattern, a pattern followed by ... matches zero or more items, and each
+// of its variables is bound to the list of the values it matched in turn.
+
+// function: adds match to the given context.
+pub fn register(symbols: &mut LispContext) {
+    symbols.insert_func("match", match_form);
+}
+
+fn is_ellipsis(token: &LispToken) -> bool {
+    token.is_sym("...")
+}
+
+// function: returns the variables bound by a pattern, in the order they appear.
+fn pattern_vars(pattern: &LispToken, vars: &mut Vec<String>) {
+    match pattern {
+        LispToken::Sym(s) if s == "_" || s == "..." || s == "#t" || s == "#f" || s == "#nil" => (),
+        LispToken::Sym(s) if !vars.contains(s) => vars.push(s.to_string()),
+        LispToken::List(xs) => {
+            let items = match xs.first() {
+                Some(head) if head.is_sym("quote") => &xs[..0],
+                Some(head) if head.is_sym("?") || head.is_sym("$") => xs.get(2..).unwrap_or(&[]),
+                _ => &xs[..]
+            };
+
+            for x in items {
+                pattern_vars(x, vars);
+            }
+        },
+        LispToken::DottedList(xs, tail) => {
+            for x in xs {
+                pattern_vars(x, vars);
+            }
+
+            pattern_vars(tail, vars);
+        },
+        LispToken::Vector(xs) => {
+            for x in xs.borrow().iter() {
+                pattern_vars(x, vars);
+            }
+        },
+        _ => ()
+    }
+}
+
+// function: binds a variable, checking that a variable bound before has an equal value.
+fn bind(bindings: &mut Vec<(String, LispToken)>, name: &str, value: &LispToken) -> bool {
+    match bindings.iter().find(|(n, _)| n == name) {
+        Some((_, bound)) => bound.is_equal(value),
+        None => {
+            bindings.push((name.to_string(), value.clone()));
+            true
+        }
+    }
+}
+
+// function: matches a value against a pattern, adding the variables it binds.
+fn match_pattern(ctx: &mut LispContext, pattern: &LispToken, value: &LispToken, bindings: &mut Vec<(String, LispToken)>) -> Result<bool, LispError> {
+    match pattern {
+        LispToken::Sym(s) if s == "_" => Ok(true),
+        LispToken::Sym(s) if s == "#t" || s == "#f" => Ok(pattern.is_equal(value)),
+        LispToken::Sym(s) if s == "#nil" => Ok(LispToken::nil().is_equal(value)),
+        LispToken::Sym(s) if s == "..." => Err(LispError::EvalError("... must follow a pattern in a list or vector.".to_string())),
+        LispToken::Sym(s) => Ok(bind(bindings, s, value)),
+        LispToken::Quote(datum) => Ok(datum.is_equal(value)),
+        LispToken::List(xs) => match xs.first() {
+            Some(head) if head.is_sym("quote") && xs.len() == 2 => Ok(xs[1].is_equal(value)),
+            Some(head) if head.is_sym("?") && xs.len() >= 2 => {
+                let pred = eval(ctx, &xs[1])?;
+                let test = apply_proc(ctx, &pred, vec![value.clone()])?;
+
+                if !ctx.is_true(&test) {
+                    return Ok(false);
+                }
+
+                for x in &xs[2..] {
+                    if !match_pattern(ctx, x, value, bindings)? {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            },
+            Some(head) if head.is_sym("$") && xs.len() >= 2 => {
+                let rtype = match eval(ctx, &xs[1])? {
+                    LispToken::RecordType(rtype) => rtype,
+                    _ => return Err(LispError::EvalError(format!("`{}` is not a record type.", xs[1])))
+                };
+
+                if xs.len() - 2 > rtype.fields.len() {
+                    return Err(LispError::EvalError(format!("{} has only {} fields.", rtype.name, rtype.fields.len())));
+                }
+
+                let values = match value {
+                    LispToken::Record(record) if Rc::ptr_eq(&record.rtype, &rtype) => record.values.borrow().clone(),
+                    _ => return Ok(false)
+                };
+
+                match_items(ctx, &xs[2..], &values, bindings)
+            },
+            _ => match value {
+                LispToken::List(ys) => match_sequence(ctx, xs, ys, bindings),
+                _ => Ok(false)
+            }
+        },
+        LispToken::DottedList(xs, tail) => {
+            let mut rest = value.clone();
+
+            for x in xs {
+                let (head, next) = match (rest.car(), rest.cdr()) {
+                    (Some(head), Some(next)) if rest.is_pair() => (head.clone(), next),
+                    _ => return Ok(false)
+                };
+
+                if !match_pattern(ctx, x, &head, bindings)? {
+                    return Ok(false);
+                }
+
+                rest = next;
+            }
+
+            match_pattern(ctx, tail, &rest, bindings)
+        },
+        LispToken::Vector(xs) => match value {
+            LispToken::Vector(ys) => {
+                let (xs, ys) = (xs.borrow().clone(), ys.borrow().clone());
+                match_sequence(ctx, &xs, &ys, bindings)
+            },
+            _ => Ok(false)
+        },
+        _ => Ok(pattern.is_equal(value))
+    }
+}
+
+// function: matches the items one to one against the patterns.
+fn match_items(ctx: &mut LispContext, patterns: &[LispToken], values: &[LispToken], bindings: &mut Vec<(String, LispToken)>) -> Result<bool, LispError> {
+    for (pattern, value) in patterns.iter().zip(values) {
+        if !match_pattern(ctx, pattern, value, bindings)? {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+// function: matches the items of a list or vector against the patterns, where a pattern
+// followed by ... matches as many of the items as are left over by the other patterns.
+fn match_sequence(ctx: &mut LispContext, patterns: &[LispToken], values: &[LispToken], bindings: &mut Vec<(String, LispToken)>) -> Result<bool, LispError> {
+    let pos = match patterns.iter().position(is_ellipsis) {
+        Some(0) => return Err(LispError::EvalError("... must follow a pattern in a list or vector.".to_string())),
+        Some(pos) => pos,
+        None => {
+            return if patterns.len() == values.len() {
+                match_items(ctx, patterns, values, bindings)
+            } else {
+                Ok(false)
+            };
+        }
+    };
+
+    let (before, repeated, after) = (&patterns[..pos - 1], &patterns[pos - 1], &patterns[pos + 1..]);
+
+    if after.iter().any(is_ellipsis) {
+        return Err(LispError::EvalError("only one ... is allowed in a list or vector pattern.".to_string()));
+    }
+
+    if values.len() < before.len() + after.len() {
+        return Ok(false);
+    }
+
+    let end = values.len() - after.len();
+
+    if !match_items(ctx, before, &values[..before.len()], bindings)? || !match_items(ctx, after, &values[end..], bindings)? {
+        return Ok(false);
+    }
+
+    let mut vars = Vec::new();
+    pattern_vars(repeated, &mut vars);
+
+    let mut columns : Vec<Vec<LispToken>> = vec![Vec::new(); vars.len()];
+
+    for value in &values[before.len()..end] {
+        let mut inner = Vec::new();
+
+        if !match_pattern(ctx, repeated, value, &mut inner)? {
+            return Ok(false);
+        }
+
+        for (var, column) in vars.iter().zip(columns.iter_mut()) {
+            if let Some((_, bound)) = inner.iter().find(|(n, _)| n == var) {
+                column.push(bound.clone());
+            }
+        }
+    }
+
+    for (var, column) in vars.iter().zip(columns) {
+        if !bind(bindings, var, &LispToken::List(column)) {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+// function: evaluates the value and runs the first clause whose pattern matches it. the body is
+// run as a lambda of the pattern variables, so it sees them like any other parameters.
+fn match_form(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.is_empty() {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    let value = eval(ctx, &args[0])?;
+
+    for clause in &args[1..] {
+        let clause = match clause {
+            LispToken::List(clause) if clause.len() >= 2 => clause,
+            _ => return Err(LispError::EvalError(format!("invalid match clause `{}`.", clause)))
+        };
+
+        let mut bindings = Vec::new();
+
+        if match_pattern(ctx, &clause[0], &value, &mut bindings)? {
+            let (names, values) : (Vec<_>, Vec<_>) = bindings.into_iter().map(|(n, v)| (LispToken::Sym(n), v)).unzip();
+
+            let mut lambda = vec![LispToken::Sym("lambda".to_string()), LispToken::List(names)];
+            lambda.extend_from_slice(&clause[1..]);
+            return apply_proc(ctx, &LispToken::List(lambda), values);
+        }
+    }
+
+    Err(LispError::EvalError(format!("no match clause matched `{}`.", value)))
+}
+
+// function: substitutes the bindings of an enclosing lambda into a match form. the variables of
+// each pattern shadow the bindings in its body, while the expressions in ? and $ patterns are
+// substituted like any other expression.
+pub(crate) fn substitute_match(xs: &[LispToken], bindings: &HashMap<String, LispToken>) -> LispToken {
+    let mut lst = vec![xs[0].clone(), substitute(&xs[1], bindings)];
+
+    for clause in &xs[2..] {
+        match clause {
+            LispToken::List(clause) if !clause.is_empty() => {
+                let mut vars = Vec::new();
+                pattern_vars(&clause[0], &mut vars);
+
+                let mut inner = bindings.clone();
+                for var in &vars {
+                    inner.remove(var);
+                }
+
+                let mut form = vec![substitute_pattern(&clause[0], bindings)];
+                form.extend(clause[1..].iter().map(|x| substitute(x, &inner)));
+                lst.push(LispToken::List(form));
+            },
+            _ => lst.push(clause.clone())
+        }
+    }
+
+    LispToken::List(lst)
+}
+
+fn substitute_pattern(pattern: &LispToken, bindings: &HashMap<String, LispToken>) -> LispToken {
+    match pattern {
+        LispToken::List(xs) => match xs.first() {
+            Some(head) if head.is_sym("quote") => pattern.clone(),
+            Some(head) if (head.is_sym("?") || head.is_sym("$")) && xs.len() >= 2 => {
+                let mut lst = vec![xs[0].clone(), substitute(&xs[1], bindings)];
+                lst.extend(xs[2..].iter().map(|x| substitute_pattern(x, bindings)));
+                LispToken::List(lst)
+            },
+            _ => LispToken::List(xs.iter().map(|x| substitute_pattern(x, bindings)).collect())
+        },
+        LispToken::DottedList(xs, tail) => {
+            let xs = xs.iter().map(|x| substitute_pattern(x, bindings)).collect();
+            LispToken::DottedList(xs, Box::new(substitute_pattern(tail, bindings)))
+        },
+        _ => pattern.clone()
+    }
+}
@@ -1,13 +1,17 @@
-use crate::tokens::{LispError, LispToken};
+use crate::hashtables::{HashTest, LispHash};
+use crate::tokens::{char_from_name, LispError, LispToken};
+
+use std::cell::{RefCell};
+use std::rc::{Rc};
 
 // function: serves to call the actual parsing function.
-pub fn parse(expr: &Vec<char>) -> Result<LispToken, LispError> {
+pub fn parse(expr: &[char]) -> Result<LispToken, LispError> {
     let mut idx = 0;
-    parse_rd(&expr, &mut idx)
+    parse_rd(expr, &mut idx)
 }
 
 // function: converts a vector of chars to a s-expression. returns LispToken on success or LispError on error.
-fn parse_rd(expr: &Vec<char>, idx: &mut usize) -> Result<LispToken, LispError> {
+fn parse_rd(expr: &[char], idx: &mut usize) -> Result<LispToken, LispError> {
     loop {
         if *idx >= expr.len() {
             return Err(LispError::EndOfSequence);
@@ -20,33 +24,37 @@ fn parse_rd(expr: &Vec<char>, idx: &mut usize) -> Result<LispToken, LispError> {
             expr[*idx + 1]
         };
 
-        if ch.is_alphabetic() || ch == '#' {
-            return symbol(&expr, idx);
+        if ch == '#' {
+            return dispatch(expr, idx);
+        } else if ch.is_alphabetic() {
+            return symbol(expr, idx);
         } else if ch.is_numeric() || (ch == '-' && ahead.is_numeric()) {
-            return number(&expr, idx);
+            return number(expr, idx);
         } else if ch == '"' {
-            return string(&expr, idx);
+            return string(expr, idx);
         } else if ch == '\'' {
-            return quote(&expr, idx);
-        } else if is_special(ch) {
-            return special(&expr, idx);
+            return quote(expr, idx);
+        } else if is_symbol_char(ch) {
+            return special(expr, idx);
         } else if ch == '(' {
-            return list(&expr, idx);
-        } else if !is_delimiter(ch) && !is_special(ch) {
+            return list(expr, idx);
+        } else if ch == '{' {
+            return hash_table(expr, idx);
+        } else if !is_delimiter(ch) {
             return Err(LispError::UnexpectedChar(ch, *idx));
         } else {
-            *idx = *idx + 1;
+            *idx += 1;
         }
     }
 }
 
 // function: reads in a sequence of numeric characters or symbols into a buffer and stores it in a Num variant
-fn number(expr: &Vec<char>, idx: &mut usize) ->  Result<LispToken, LispError> {
+fn number(expr: &[char], idx: &mut usize) ->  Result<LispToken, LispError> {
     let mut s = expr[*idx].to_string();
     let mut decimal_set = false;
 
     loop {
-        *idx = *idx + 1;
+        *idx += 1;
 
         if *idx >= expr.len() {
             return Err(LispError::EndOfSequence);
@@ -60,7 +68,7 @@ fn number(expr: &Vec<char>, idx: &mut usize) ->  Result<LispToken, LispError> {
             s.push(ch);
             decimal_set = true;
         } else if is_delimiter(ch) {
-            *idx = *idx - 1;
+            *idx -= 1;
             break;
         } else if !is_bracket(ch) {
             return Err(LispError::UnexpectedChar(ch, *idx));
@@ -70,20 +78,91 @@ fn number(expr: &Vec<char>, idx: &mut usize) ->  Result<LispToken, LispError> {
     Ok(LispToken::Num(s))
 }
 
-// function: reads in the next LispToken stores them in a Quote variant using the format! macro.
-fn quote(expr: &Vec<char>, idx: &mut usize) ->  Result<LispToken, LispError> {
-    *idx = *idx + 1;
+// function: reads in the next LispToken and stores it in a Quote variant.
+fn quote(expr: &[char], idx: &mut usize) ->  Result<LispToken, LispError> {
+    *idx += 1;
 
     let value = parse_rd(expr, idx)?;
-    Ok(LispToken::Quote(format!("{} ", value)))
+    Ok(LispToken::Quote(Box::new(value)))
+}
+
+// function: dispatches on the characters following a # to read character literals, vectors, bytevectors
+// or #-prefixed symbols such as #t.
+fn dispatch(expr: &[char], idx: &mut usize) -> Result<LispToken, LispError> {
+    match expr.get(*idx + 1) {
+        Some('\\') => character(expr, idx),
+        Some('(') => vector(expr, idx),
+        Some('u') if expr[*idx + 2..].starts_with(&['8', '(']) => bytevector(expr, idx),
+        _ => symbol(expr, idx)
+    }
+}
+
+// function: reads a bytevector literal #u8(...) and stores its bytes in a Bytes variant.
+fn bytevector(expr: &[char], idx: &mut usize) -> Result<LispToken, LispError> {
+    let start = *idx;
+    *idx += 3;
+
+    let bytes = match list(expr, idx)? {
+        LispToken::List(xs) => xs.iter().map(|x| x.to_byte()).collect::<Result<Vec<u8>, LispError>>(),
+        _ => return Err(LispError::Other("a bytevector literal can't be a dotted list".to_string()))
+    };
+
+    match bytes {
+        Ok(bytes) => Ok(LispToken::Bytes(Rc::new(RefCell::new(bytes)))),
+        Err(_) => Err(LispError::Other(format!("bytevector literal at col {} holds a value that is not a byte", start)))
+    }
+}
+
+// function: reads a vector literal #(...) and stores its items in a Vector variant.
+fn vector(expr: &[char], idx: &mut usize) -> Result<LispToken, LispError> {
+    *idx += 1;
+
+    match list(expr, idx)? {
+        LispToken::List(xs) => Ok(LispToken::Vector(Rc::new(RefCell::new(xs)))),
+        _ => Err(LispError::Other("a vector literal can't be a dotted list".to_string()))
+    }
+}
+
+// function: reads a character literal such as #\a, #\space or #\x41 and stores it in a Char variant.
+fn character(expr: &[char], idx: &mut usize) -> Result<LispToken, LispError> {
+    *idx += 2;
+
+    if *idx >= expr.len() {
+        return Err(LispError::EndOfSequence);
+    }
+
+    // the first character is always part of the literal, so that #\( and #\space both read.
+    let mut name = expr[*idx].to_string();
+
+    loop {
+        *idx += 1;
+
+        if *idx >= expr.len() {
+            return Err(LispError::EndOfSequence);
+        }
+
+        let ch = expr[*idx];
+
+        if is_delimiter(ch) {
+            *idx -= 1;
+            break;
+        }
+
+        name.push(ch);
+    }
+
+    match char_from_name(&name) {
+        Some(ch) => Ok(LispToken::Char(ch)),
+        None => Err(LispError::Other(format!("unknown character `#\\{}`", name)))
+    }
 }
 
 // function: reads in alphanumeric characters and stores them in a Sym variant.
-fn symbol(expr: &Vec<char>, idx: &mut usize) ->  Result<LispToken, LispError> {
+fn symbol(expr: &[char], idx: &mut usize) ->  Result<LispToken, LispError> {
     let mut s = expr[*idx].to_string();
 
     loop {
-        *idx = *idx + 1;
+        *idx += 1;
 
         if *idx >= expr.len() {
             return Err(LispError::EndOfSequence);
@@ -91,10 +170,10 @@ fn symbol(expr: &Vec<char>, idx: &mut usize) ->  Result<LispToken, LispError> {
 
         let ch = expr[*idx];
 
-        if ch.is_alphanumeric() || ch == '-' || ch == '#' {
+        if ch.is_alphanumeric() || ch == '#' || is_symbol_char(ch) {
             s.push(ch);
         } else if is_delimiter(ch) {
-            *idx = *idx - 1;
+            *idx -= 1;
             break;
         } else {
             return Err(LispError::UnexpectedChar(ch, *idx));
@@ -105,12 +184,12 @@ fn symbol(expr: &Vec<char>, idx: &mut usize) ->  Result<LispToken, LispError> {
 }
 
 // function: reads in special characters or symbols and stores them in a Sym variant.
-fn special(expr: &Vec<char>, idx: &mut usize) -> Result<LispToken, LispError> {
+// a symbol may continue after its special characters, as in <point>, ->string or _tmp.
+fn special(expr: &[char], idx: &mut usize) -> Result<LispToken, LispError> {
     let mut s = expr[*idx].to_string();
-    let mut not_set = true;
 
     loop {
-        *idx = *idx + 1;
+        *idx += 1;
 
         if *idx >= expr.len() {
             return Err(LispError::EndOfSequence);
@@ -118,11 +197,10 @@ fn special(expr: &Vec<char>, idx: &mut usize) -> Result<LispToken, LispError> {
 
         let ch = expr[*idx];
 
-        if not_set && is_special(ch) {
+        if ch.is_alphanumeric() || is_symbol_char(ch) {
             s.push(ch);
-            not_set = false;
         } else if is_delimiter(ch) {
-            *idx = *idx - 1;
+            *idx -= 1;
             return Ok(LispToken::Sym(s));
         } else {
             return Err(LispError::UnexpectedChar(ch, *idx));
@@ -130,32 +208,146 @@ fn special(expr: &Vec<char>, idx: &mut usize) -> Result<LispToken, LispError> {
     }
 }
 
-// function: reads in a sequence of characters, starting and ending with " and stores them in a Str variant.
-fn string(expr: &Vec<char>, idx: &mut usize) -> Result<LispToken, LispError> {
-    let mut s = expr[*idx].to_string();
+// function: reads in a sequence of characters, starting and ending with " and stores their contents in a Str variant.
+// the escapes \n \t \r \\ \" \xHH; and \u{HHHH} are replaced by the characters they stand for.
+fn string(expr: &[char], idx: &mut usize) -> Result<LispToken, LispError> {
+    let mut s = String::new();
+
+    loop {
+        *idx += 1;
+
+        if *idx >= expr.len() {
+            return Err(LispError::EndOfSequence);
+        }
+
+        match expr[*idx] {
+            '"' => return Ok(LispToken::Str(s)),
+            '\\' => s.push(escape(expr, idx)?),
+            ch => s.push(ch)
+        }
+    }
+}
+
+// function: reads the escape sequence following a backslash and returns the character it stands for.
+fn escape(expr: &[char], idx: &mut usize) -> Result<char, LispError> {
+    *idx += 1;
+
+    if *idx >= expr.len() {
+        return Err(LispError::EndOfSequence);
+    }
+
+    match expr[*idx] {
+        'n' => Ok('\n'),
+        't' => Ok('\t'),
+        'r' => Ok('\r'),
+        '\\' => Ok('\\'),
+        '"' => Ok('"'),
+        'x' => hex_char(expr, idx, ';'),
+        'u' => {
+            *idx += 1;
+
+            if *idx >= expr.len() {
+                return Err(LispError::EndOfSequence);
+            } else if expr[*idx] != '{' {
+                return Err(LispError::UnexpectedChar(expr[*idx], *idx));
+            }
+
+            hex_char(expr, idx, '}')
+        },
+        ch => Err(LispError::UnexpectedChar(ch, *idx))
+    }
+}
+
+// function: reads hexadecimal digits up to the terminator and converts them to a character.
+fn hex_char(expr: &[char], idx: &mut usize, terminator: char) -> Result<char, LispError> {
+    let start = *idx;
+    let mut digits = String::new();
 
     loop {
-        *idx = *idx + 1;
+        *idx += 1;
 
         if *idx >= expr.len() {
             return Err(LispError::EndOfSequence);
         }
 
         let ch = expr[*idx];
-        s.push(ch);
 
-        if ch == '"' {
-            return Ok(LispToken::Str(s));
+        if ch == terminator {
+            break;
+        } else if ch.is_ascii_hexdigit() {
+            digits.push(ch);
+        } else {
+            return Err(LispError::UnexpectedChar(ch, *idx));
         }
     }
+
+    u32::from_str_radix(&digits, 16).ok()
+        .and_then(std::char::from_u32)
+        .ok_or_else(|| LispError::Other(format!("invalid character escape at col {}", start)))
+}
+
+// function: reads a hash table literal {key value ...} and stores its entries in a Hash variant comparing keys with equal?.
+fn hash_table(expr: &[char], idx: &mut usize) -> Result<LispToken, LispError> {
+    let mut table = LispHash::new(HashTest::Equal);
+    let mut key = None;
+
+    loop {
+        *idx += 1;
+
+        while *idx < expr.len() && expr[*idx].is_whitespace() {
+            *idx += 1;
+        }
+
+        if *idx >= expr.len() {
+            return Err(LispError::Other("expected closing '}'".to_string()));
+        }
+
+        if expr[*idx] == '}' {
+            break;
+        }
+
+        let token = parse_rd(expr, idx)?;
+
+        match key.take() {
+            Some(key) => table.insert(key, token),
+            None => key = Some(token)
+        }
+    }
+
+    if key.is_some() {
+        return Err(LispError::Other("hash table literal has a key without a value".to_string()));
+    }
+
+    Ok(LispToken::Hash(Rc::new(RefCell::new(table))))
+}
+
+// function: reads the value following the dot of a dotted list such as (a b . c) and conses the items onto it.
+fn dotted_tail(expr: &[char], idx: &mut usize, items: Vec<LispToken>) -> Result<LispToken, LispError> {
+    *idx += 1;
+    let tail = parse_rd(expr, idx)?;
+    *idx += 1;
+
+    while *idx < expr.len() && expr[*idx].is_whitespace() {
+        *idx += 1;
+    }
+
+    match expr.get(*idx) {
+        Some(')') => Ok(items.into_iter().rev().fold(tail, |tail, head| LispToken::cons(head, tail))),
+        Some(ch) => Err(LispError::UnexpectedChar(*ch, *idx)),
+        None => Err(LispError::Other("expected closing ')".to_string()))
+    }
 }
 
 // function: stores LispTokens from parse_rd in a vector and stores them in a List variant.
-fn list(expr: &Vec<char>, idx: &mut usize) ->  Result<LispToken, LispError> {
+fn list(expr: &[char], idx: &mut usize) ->  Result<LispToken, LispError> {
     let mut lst = Vec::new();
 
     loop {
-        *idx = *idx + 1;
+        *idx += 1;
+
+        while *idx < expr.len() && expr[*idx].is_whitespace() {
+            *idx += 1;
+        }
 
         if *idx >= expr.len() {
             return Err(LispError::Other("expected closing ')".to_string()));
@@ -165,7 +357,11 @@ fn list(expr: &Vec<char>, idx: &mut usize) ->  Result<LispToken, LispError> {
             break;
         }
 
-        let token = parse_rd(&expr, idx)?;
+        if expr[*idx] == '.' && !lst.is_empty() && expr.get(*idx + 1).is_some_and(|ch| is_delimiter(*ch)) {
+            return dotted_tail(expr, idx, lst);
+        }
+
+        let token = parse_rd(expr, idx)?;
         lst.push(token);
     }
 
@@ -175,11 +371,15 @@ fn list(expr: &Vec<char>, idx: &mut usize) ->  Result<LispToken, LispError> {
 // Helper Functions
 
 fn is_bracket(ch: char) -> bool {
-    ch == '(' || ch == ')'
+    "(){}".contains(ch)
 }
 
 fn is_special(ch: char) -> bool {
-    "+-*/%<>".contains(ch)
+    "+-*/%<>=".contains(ch)
+}
+
+fn is_symbol_char(ch: char) -> bool {
+    is_special(ch) || "!?_$.".contains(ch)
 }
 
 fn is_delimiter(ch: char) -> bool {
@@ -0,0 +1,123 @@
+use crate::context::{LispContext};
+use crate::eval::{eval_args, LispResult};
+use crate::tokens::{LispError, LispPort, LispToken, PrintMode};
+
+use std::cell::{RefCell};
+use std::rc::{Rc};
+
+// Output functions take an optional output port as their last argument and write to the
+// standard output when it is omitted. String ports collect their output in memory so that
+// large texts can be built without repeated string-append calls.
+
+// function: adds the output port functions to the given context.
+pub fn register(symbols: &mut LispContext) {
+    symbols.insert_func("display", display);
+    symbols.insert_func("write", write);
+    symbols.insert_func("newline", newline);
+    symbols.insert_func("write-string", write_string);
+    symbols.insert_func("write-char", write_char);
+
+    symbols.insert_func("output-port?", is_output_port);
+    symbols.insert_func("current-output-port", current_output_port);
+    symbols.insert_func("open-output-string", open_output_string);
+    symbols.insert_func("get-output-string", get_output_string);
+}
+
+// function: writes text to the given port, or to the standard output when no port is given.
+// the output is a side effect, so the results of the enclosing expressions are not memoized.
+pub(crate) fn write_to(ctx: &mut LispContext, port: Option<&LispToken>, text: &str) -> Result<(), LispError> {
+    match port {
+        None => {
+            print!("{}", text);
+            ctx.record_effect();
+        },
+        Some(LispToken::Port(port)) => {
+            port.borrow_mut().write_str(text);
+
+            // text written to a string port can be read back, so memoized results are discarded.
+            match &*port.borrow() {
+                LispPort::Stdout => ctx.record_effect(),
+                LispPort::Str(_) => ctx.invalidate()
+            }
+        },
+        Some(_) => return Err(LispError::EvalError("value is not an output port.".to_string()))
+    }
+
+    Ok(())
+}
+
+// function: prints a value in the given mode.
+fn print_value(ctx: &mut LispContext, args: &[LispToken], mode: PrintMode) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 2)?;
+    write_to(ctx, xs.get(1), &format!("{}", xs[0].printed(mode)))?;
+    Ok(LispToken::nil())
+}
+
+// function: prints a value with strings and characters shown as their raw contents.
+fn display(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    print_value(ctx, args, PrintMode::Display)
+}
+
+// function: prints a value so that it reads back as the same value.
+fn write(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    print_value(ctx, args, PrintMode::Write)
+}
+
+fn newline(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 0, 1)?;
+    write_to(ctx, xs.first(), "\n")?;
+    Ok(LispToken::nil())
+}
+
+fn write_string(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 2)?;
+    write_to(ctx, xs.get(1), &xs[0].to_str()?)?;
+    Ok(LispToken::nil())
+}
+
+fn write_char(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 2)?;
+    write_to(ctx, xs.get(1), &xs[0].to_char()?.to_string())?;
+    Ok(LispToken::nil())
+}
+
+fn is_output_port(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+
+    match xs[0] {
+        LispToken::Port(_) => Ok(LispToken::from(true)),
+        _ => Ok(LispToken::from(false))
+    }
+}
+
+// function: returns a port writing to the standard output.
+fn current_output_port(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    eval_args(ctx, args, 0, 0)?;
+    Ok(LispToken::Port(Rc::new(RefCell::new(LispPort::Stdout))))
+}
+
+// function: returns a new string port accumulating the output written to it.
+fn open_output_string(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    eval_args(ctx, args, 0, 0)?;
+
+    // every call creates a distinct port, so the result must not be memoized.
+    ctx.record_effect();
+    Ok(LispToken::Port(Rc::new(RefCell::new(LispPort::Str(String::new())))))
+}
+
+// function: returns everything written to a string port so far.
+fn get_output_string(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+
+    // string ports all print alike, so the text depends on the identity of the port, which
+    // isn't part of the expression.
+    ctx.record_effect();
+
+    if let LispToken::Port(port) = &xs[0] {
+        if let LispPort::Str(buffer) = &*port.borrow() {
+            return Ok(LispToken::Str(buffer.to_string()));
+        }
+    }
+
+    Err(LispError::EvalError("value is not a string port.".to_string()))
+}
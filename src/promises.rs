@@ -0,0 +1,241 @@
+use crate::context::{LispContext};
+use crate::eval::{apply_proc, eval, eval_args, quote_value, LispResult};
+use crate::tokens::{LispError, LispToken};
+
+use std::cell::{RefCell};
+use std::rc::{Rc};
+
+// A promise holds an expression that is evaluated the first time the promise is forced, after
+// which the value is kept and returned by every later force.
+//
+// Streams are pairs whose cdr is a promise, so (stream-cons a b) only evaluates b when the
+// rest of the stream is needed, and infinite streams only hold the items forced so far.
+// The empty stream is the empty list, also bound to stream-null.
+
+// enum: the state of a promise.
+// Delayed: the expression to evaluate, and whether it is a delay-force whose value is another promise.
+// Forced: the value of the expression.
+pub enum LispPromise {
+    Delayed(LispToken, bool),
+    Forced(LispToken)
+}
+
+// function: adds promises and the stream library to the given context.
+pub fn register(symbols: &mut LispContext) {
+    symbols.insert_func("delay", delay);
+    symbols.insert_func("delay-force", delay_force);
+    symbols.insert_func("make-promise", make_promise);
+    symbols.insert_func("force", force);
+    symbols.insert_func("promise?", is_promise);
+    symbols.insert("stream-null", LispToken::nil());
+    symbols.insert_func("stream-cons", stream_cons);
+    symbols.insert_func("stream-null?", is_stream_null);
+    symbols.insert_func("stream-pair?", is_stream_pair);
+    symbols.insert_func("stream-car", stream_car);
+    symbols.insert_func("stream-cdr", stream_cdr);
+    symbols.insert_func("stream-take", stream_take);
+    symbols.insert_func("stream-map", stream_map);
+    symbols.insert_func("stream-filter", stream_filter);
+}
+
+// function: returns a new promise in the given state. every call creates a distinct object,
+// so the result must not be memoized.
+fn new_promise(ctx: &mut LispContext, state: LispPromise) -> LispToken {
+    ctx.record_effect();
+    LispToken::Promise(Rc::new(RefCell::new(state)))
+}
+
+// function: forces a promise, returning its value. a delay-force takes over the state of the
+// promise its expression returns and carries on forcing it in the same loop, so chains of
+// delay-force run in constant stack space. values other than promises are returned as they are.
+pub(crate) fn force_value(ctx: &mut LispContext, value: &LispToken) -> LispResult {
+    let promise = match value {
+        LispToken::Promise(promise) => promise,
+        _ => return Ok(value.clone())
+    };
+
+    // the value depends on the identity of the promise, which isn't part of its printed form.
+    ctx.record_effect();
+
+    loop {
+        let (expr, lazy) = match &*promise.borrow() {
+            LispPromise::Forced(value) => return Ok(value.clone()),
+            LispPromise::Delayed(expr, lazy) => (expr.clone(), *lazy)
+        };
+
+        let value = eval(ctx, &expr)?;
+
+        // forcing the expression may have forced this promise already.
+        if let LispPromise::Forced(value) = &*promise.borrow() {
+            return Ok(value.clone());
+        }
+
+        let state = match (lazy, &value) {
+            (true, LispToken::Promise(inner)) => match &*inner.borrow() {
+                LispPromise::Forced(value) => LispPromise::Forced(value.clone()),
+                LispPromise::Delayed(expr, lazy) => LispPromise::Delayed(expr.clone(), *lazy)
+            },
+            (true, _) => return Err(LispError::EvalError("delay-force expression did not return a promise.".to_string())),
+            (false, _) => LispPromise::Forced(value)
+        };
+
+        *promise.borrow_mut() = state;
+    }
+}
+
+// function: returns a promise evaluating the expression when forced.
+fn delay(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() != 1 {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    Ok(new_promise(ctx, LispPromise::Delayed(args[0].clone(), false)))
+}
+
+// function: returns a promise whose expression evaluates to another promise, which is forced in turn.
+fn delay_force(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() != 1 {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    Ok(new_promise(ctx, LispPromise::Delayed(args[0].clone(), true)))
+}
+
+// function: returns an already forced promise of the value, or the value if it is a promise.
+fn make_promise(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+
+    match &xs[0] {
+        LispToken::Promise(_) => Ok(xs[0].clone()),
+        value => Ok(new_promise(ctx, LispPromise::Forced(value.clone())))
+    }
+}
+
+fn force(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    force_value(ctx, &xs[0])
+}
+
+fn is_promise(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+
+    match xs[0] {
+        LispToken::Promise(_) => Ok(LispToken::from(true)),
+        _ => Ok(LispToken::from(false))
+    }
+}
+
+// function: returns the head and the promise of the rest of a stream pair.
+fn to_stream_pair(token: &LispToken) -> Result<(&LispToken, &LispToken), LispError> {
+    match token {
+        LispToken::DottedList(xs, rest) if xs.len() == 1 && matches!(**rest, LispToken::Promise(_)) => Ok((&xs[0], rest)),
+        _ => Err(LispError::EvalError("value is not a stream pair.".to_string()))
+    }
+}
+
+// function: returns a stream pair of the head and a promise of the rest.
+fn make_stream(ctx: &mut LispContext, head: LispToken, rest: LispToken) -> LispToken {
+    let promise = new_promise(ctx, LispPromise::Delayed(rest, false));
+    LispToken::cons(head, promise)
+}
+
+// function: evaluates the head of a stream pair, delaying the evaluation of the rest.
+fn stream_cons(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() != 2 {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    let head = eval(ctx, &args[0])?;
+    Ok(make_stream(ctx, head, args[1].clone()))
+}
+
+fn is_stream_null(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(LispToken::from(xs[0].is_nil()))
+}
+
+fn is_stream_pair(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(LispToken::from(to_stream_pair(&xs[0]).is_ok()))
+}
+
+fn stream_car(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(to_stream_pair(&xs[0])?.0.clone())
+}
+
+// function: forces and returns the rest of a stream.
+fn stream_cdr(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    let rest = to_stream_pair(&xs[0])?.1;
+    force_value(ctx, rest)
+}
+
+// function: returns a list of the first n items of a stream, or all of them if there are fewer.
+fn stream_take(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let n = xs[1].to_index()?;
+    let mut stream = xs[0].clone();
+    let mut result = Vec::new();
+
+    while result.len() < n && !stream.is_nil() {
+        let (head, rest) = to_stream_pair(&stream)?;
+        result.push(head.clone());
+
+        let rest = rest.clone();
+        stream = force_value(ctx, &rest)?;
+    }
+
+    Ok(LispToken::List(result))
+}
+
+// function: returns the expression applying a stream function to the procedure and the rest of
+// each stream, used as the delayed rest of the stream it returns.
+fn rest_expr(func: LispToken, proc: &LispToken, streams: &[LispToken]) -> LispToken {
+    let mut expr = vec![func, quote_value(proc)];
+    expr.extend(streams.iter().map(|s| LispToken::List(vec![LispToken::Func("stream-cdr", stream_cdr), quote_value(s)])));
+    LispToken::List(expr)
+}
+
+// function: returns a stream of the results of calling the procedure on the items of the
+// streams, stopping at the end of the shortest one. the procedure is only called on an item
+// once the stream reaches it.
+fn stream_map(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, usize::MAX)?;
+    let streams = &xs[1..];
+
+    if streams.iter().any(|s| s.is_nil()) {
+        return Ok(LispToken::nil());
+    }
+
+    let mut heads = Vec::new();
+    for s in streams {
+        heads.push(to_stream_pair(s)?.0.clone());
+    }
+
+    let head = apply_proc(ctx, &xs[0], heads)?;
+    let rest = rest_expr(LispToken::Func("stream-map", stream_map), &xs[0], streams);
+    Ok(make_stream(ctx, head, rest))
+}
+
+// function: returns a stream of the items for which the predicate is true. items are skipped
+// in a loop until one is found, so long runs of rejected items don't use up the stack.
+fn stream_filter(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let mut stream = xs[1].clone();
+
+    while !stream.is_nil() {
+        let (head, rest) = to_stream_pair(&stream)?;
+        let (head, rest) = (head.clone(), rest.clone());
+        let test = apply_proc(ctx, &xs[0], vec![head.clone()])?;
+
+        if ctx.is_true(&test) {
+            let rest = rest_expr(LispToken::Func("stream-filter", stream_filter), &xs[0], &[stream]);
+            return Ok(make_stream(ctx, head, rest));
+        }
+
+        stream = force_value(ctx, &rest)?;
+    }
+
+    Ok(LispToken::nil())
+}
@@ -0,0 +1,193 @@
+use crate::context::{LispContext};
+use crate::eval::{eval_args, LispResult};
+use crate::tokens::{LispError, LispToken};
+
+use std::cell::{RefCell};
+use std::rc::{Rc};
+
+// (define-record-type point (make-point x y) point? (x point-x set-point-x!) (y point-y))
+// defines a record type with the fields x and y, binding the constructor, the predicate and
+// the accessor and optional modifier of each field. The type name may also be written <point>.
+//
+// The generated procedures are lambdas calling builtins on the record type, so each
+// definition creates a new type, distinct from any other type of the same name.
+
+// struct: a record type.
+// name: the name of the type, without any enclosing angle brackets.
+// fields: the names of the fields, in order.
+pub struct LispRecordType {
+    pub name: String,
+    pub fields: Vec<String>
+}
+
+// struct: an instance of a record type.
+// rtype: the type of the record.
+// values: the values of the fields, in the order of the fields of the type.
+pub struct LispRecord {
+    pub rtype: Rc<LispRecordType>,
+    pub values: RefCell<Vec<LispToken>>
+}
+
+// function: adds define-record-type and record? to the given context.
+pub fn register(symbols: &mut LispContext) {
+    symbols.insert_func("define-record-type", define_record_type);
+    symbols.insert_func("record?", is_record);
+}
+
+fn to_symbol(token: &LispToken) -> Result<&str, LispError> {
+    match token {
+        LispToken::Sym(s) => Ok(s),
+        _ => Err(LispError::EvalError(format!("expected a symbol in define-record-type, got `{}`.", token)))
+    }
+}
+
+fn to_record_type(token: &LispToken) -> Result<&Rc<LispRecordType>, LispError> {
+    match token {
+        LispToken::RecordType(rtype) => Ok(rtype),
+        _ => Err(LispError::EvalError("value is not a record type.".to_string()))
+    }
+}
+
+// function: returns the record if it is an instance of the type.
+fn to_record<'a>(token: &'a LispToken, rtype: &Rc<LispRecordType>) -> Result<&'a Rc<LispRecord>, LispError> {
+    match token {
+        LispToken::Record(record) if Rc::ptr_eq(&record.rtype, rtype) => Ok(record),
+        _ => Err(LispError::EvalError(format!("value is not a {} record.", rtype.name)))
+    }
+}
+
+fn field_index(rtype: &LispRecordType, field: &LispToken) -> Result<usize, LispError> {
+    let name = to_symbol(field)?;
+
+    match rtype.fields.iter().position(|f| f == name) {
+        Some(idx) => Ok(idx),
+        None => Err(LispError::EvalError(format!("{} has no field `{}`.", rtype.name, name)))
+    }
+}
+
+// function: returns the lambda (lambda (params) (func leading... params)) defining a generated procedure.
+fn generated(func: LispToken, leading: Vec<LispToken>, params: Vec<LispToken>) -> LispToken {
+    let mut call = vec![func];
+    call.extend(leading);
+    call.extend(params.iter().cloned());
+
+    LispToken::List(vec![LispToken::Sym("lambda".to_string()), LispToken::List(params), LispToken::List(call)])
+}
+
+fn quoted(token: &LispToken) -> LispToken {
+    LispToken::Quote(Box::new(token.clone()))
+}
+
+// function: defines a record type along with its constructor, predicate, accessors and modifiers.
+fn define_record_type(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    if args.len() < 3 {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    let name = to_symbol(&args[0])?;
+    let name = name.strip_prefix('<').and_then(|n| n.strip_suffix('>')).unwrap_or(name);
+
+    let specs = args[3..].iter().map(|spec| match spec {
+        LispToken::List(spec) if !spec.is_empty() && spec.len() <= 3 => Ok(spec.as_slice()),
+        _ => Err(LispError::EvalError(format!("invalid field spec `{}` in define-record-type.", spec)))
+    }).collect::<Result<Vec<_>, LispError>>()?;
+
+    let fields = specs.iter().map(|spec| to_symbol(&spec[0]).map(str::to_string)).collect::<Result<Vec<_>, LispError>>()?;
+    let rtype = Rc::new(LispRecordType { name: name.to_string(), fields });
+    let rtype_token = LispToken::RecordType(rtype.clone());
+
+    // the constructor takes the fields it names, in its own order.
+    if let LispToken::List(ctor) = &args[1] {
+        let (ctor_name, params) = match ctor.split_first() {
+            Some((ctor_name, params)) => (to_symbol(ctor_name)?, params.to_vec()),
+            None => return Err(LispError::EvalError("missing constructor name in define-record-type.".to_string()))
+        };
+
+        for param in &params {
+            field_index(&rtype, param)?;
+        }
+
+        let leading = vec![rtype_token.clone(), quoted(&LispToken::List(params.clone()))];
+        ctx.insert(ctor_name, generated(LispToken::Func("make-record", make_record), leading, params));
+    }
+
+    let obj = LispToken::Sym("obj".to_string());
+    let value = LispToken::Sym("value".to_string());
+
+    if let LispToken::Sym(pred) = &args[2] {
+        ctx.insert(pred.as_str(), generated(LispToken::Func("record-is?", record_is), vec![rtype_token.clone()], vec![obj.clone()]));
+    }
+
+    for spec in &specs {
+        if let Some(accessor) = spec.get(1) {
+            let leading = vec![rtype_token.clone(), quoted(&spec[0])];
+            let lambda = generated(LispToken::Func("record-get", record_get), leading, vec![obj.clone()]);
+            ctx.insert(to_symbol(accessor)?, lambda);
+        }
+
+        if let Some(modifier) = spec.get(2) {
+            let leading = vec![rtype_token.clone(), quoted(&spec[0])];
+            let lambda = generated(LispToken::Func("record-set!", record_set), leading, vec![obj.clone(), value.clone()]);
+            ctx.insert(to_symbol(modifier)?, lambda);
+        }
+    }
+
+    ctx.insert(to_symbol(&args[0])?, rtype_token.clone());
+    Ok(rtype_token)
+}
+
+// function: (make-record rtype '(field ...) value ...) returns a new record with the named
+// fields set to the values and all other fields set to #f.
+fn make_record(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, usize::MAX)?;
+    let rtype = to_record_type(&xs[0])?;
+    let mut values = vec![LispToken::from(false); rtype.fields.len()];
+
+    let names = match &xs[1] {
+        LispToken::List(names) if names.len() == xs.len() - 2 => names,
+        _ => return Err(LispError::InvalidNoArguments)
+    };
+
+    for (name, value) in names.iter().zip(&xs[2..]) {
+        values[field_index(rtype, name)?] = value.clone();
+    }
+
+    // every call creates a distinct object, so the result must not be memoized.
+    ctx.record_effect();
+    Ok(LispToken::Record(Rc::new(LispRecord { rtype: rtype.clone(), values: RefCell::new(values) })))
+}
+
+fn record_is(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let rtype = to_record_type(&xs[0])?;
+    Ok(LispToken::from(to_record(&xs[1], rtype).is_ok()))
+}
+
+fn record_get(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 3, 3)?;
+    let rtype = to_record_type(&xs[0])?;
+    let idx = field_index(rtype, &xs[1])?;
+    let record = to_record(&xs[2], rtype)?;
+    let value = record.values.borrow()[idx].clone();
+    Ok(value)
+}
+
+fn record_set(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 4, 4)?;
+    let rtype = to_record_type(&xs[0])?;
+    let idx = field_index(rtype, &xs[1])?;
+    to_record(&xs[2], rtype)?.values.borrow_mut()[idx] = xs[3].clone();
+
+    // memoized results may have read the old value.
+    ctx.invalidate();
+    Ok(LispToken::nil())
+}
+
+fn is_record(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+
+    match xs[0] {
+        LispToken::Record(_) => Ok(LispToken::from(true)),
+        _ => Ok(LispToken::from(false))
+    }
+}
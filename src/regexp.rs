@@ -0,0 +1,163 @@
+use crate::context::{LispContext};
+use crate::eval::{apply_proc, eval_args, LispResult};
+use crate::tokens::{LispError, LispToken};
+
+use regex::{Captures, Regex};
+use std::cell::{OnceCell};
+use std::rc::{Rc};
+
+// Patterns use the syntax of the regex crate. Every function accepts either a compiled
+// regex created by (regex pattern) or a pattern string, which is compiled on each call, so
+// patterns used repeatedly should be compiled once and kept in a variable.
+//
+// Matches are returned as lists holding the text of the whole match followed by the text
+// of each capture group, with #f for groups that didn't participate in the match.
+
+// struct: a compiled regular expression.
+// pattern: the source of the expression.
+// search: the expression matching anywhere in a string.
+// anchored: the expression only matching a whole string, compiled when first used.
+pub struct LispRegex {
+    pub pattern: String,
+    search: Regex,
+    anchored: OnceCell<Regex>
+}
+
+impl LispRegex {
+    pub fn new(pattern: &str) -> Result<LispRegex, LispError> {
+        Ok(LispRegex {
+            pattern: pattern.to_string(),
+            search: compile(pattern)?,
+            anchored: OnceCell::new()
+        })
+    }
+
+    fn anchored(&self) -> Result<&Regex, LispError> {
+        if let Some(re) = self.anchored.get() {
+            return Ok(re);
+        }
+
+        let re = compile(&format!(r"\A(?:{})\z", self.pattern))?;
+        Ok(self.anchored.get_or_init(|| re))
+    }
+}
+
+fn compile(pattern: &str) -> Result<Regex, LispError> {
+    Regex::new(pattern).map_err(|err| LispError::EvalError(format!("invalid regex: {}", err)))
+}
+
+// function: adds the regular expression functions to the given context.
+pub fn register(symbols: &mut LispContext) {
+    symbols.insert_func("regex", regex);
+    symbols.insert_func("regex?", is_regex);
+    symbols.insert_func("regex-match", regex_match);
+    symbols.insert_func("regex-search", regex_search);
+    symbols.insert_func("regex-match-all", regex_match_all);
+    symbols.insert_func("regex-replace", regex_replace);
+    symbols.insert_func("regex-split", regex_split);
+}
+
+// function: returns the compiled regex given as a value, compiling pattern strings.
+fn to_regex(token: &LispToken) -> Result<Rc<LispRegex>, LispError> {
+    match token {
+        LispToken::Regex(re) => Ok(re.clone()),
+        LispToken::Str(_) | LispToken::MutStr(_) => Ok(Rc::new(LispRegex::new(&token.to_str()?)?)),
+        _ => Err(LispError::EvalError("value is not a regex.".to_string()))
+    }
+}
+
+// function: converts the capture groups of a match to a list.
+fn captures_to_list(caps: &Captures) -> LispToken {
+    let groups = caps.iter().map(|group| match group {
+        Some(m) => LispToken::Str(m.as_str().to_string()),
+        None => LispToken::from(false)
+    }).collect();
+
+    LispToken::List(groups)
+}
+
+// function: compiles a pattern string into a regex value.
+fn regex(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(LispToken::Regex(Rc::new(LispRegex::new(&xs[0].to_str()?)?)))
+}
+
+fn is_regex(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+
+    match xs[0] {
+        LispToken::Regex(_) => Ok(LispToken::from(true)),
+        _ => Ok(LispToken::from(false))
+    }
+}
+
+// function: matches the regex against the whole string, returning the captures or #f.
+fn regex_match(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let re = to_regex(&xs[0])?;
+
+    match re.anchored()?.captures(&xs[1].to_str()?) {
+        Some(caps) => Ok(captures_to_list(&caps)),
+        None => Ok(LispToken::from(false))
+    }
+}
+
+// function: finds the first match of the regex in the string, returning the captures or #f.
+fn regex_search(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let re = to_regex(&xs[0])?;
+
+    match re.search.captures(&xs[1].to_str()?) {
+        Some(caps) => Ok(captures_to_list(&caps)),
+        None => Ok(LispToken::from(false))
+    }
+}
+
+// function: returns the captures of every non-overlapping match of the regex in the string.
+fn regex_match_all(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let re = to_regex(&xs[0])?;
+    let s = xs[1].to_str()?;
+
+    Ok(LispToken::List(re.search.captures_iter(&s).map(|caps| captures_to_list(&caps)).collect()))
+}
+
+// function: replaces every match of the regex in the string. the replacement is either a
+// string, in which $1 or ${name} refer to capture groups, or a procedure called with the
+// list of captures and returning the replacement text.
+fn regex_replace(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 3, 3)?;
+    let re = to_regex(&xs[0])?;
+    let s = xs[1].to_str()?;
+
+    match &xs[2] {
+        LispToken::Str(_) | LispToken::MutStr(_) => {
+            Ok(LispToken::Str(re.search.replace_all(&s, &*xs[2].to_str()?).into_owned()))
+        },
+        proc => {
+            let mut result = String::new();
+            let mut last = 0;
+
+            for caps in re.search.captures_iter(&s) {
+                let m = caps.get(0).unwrap();
+                let replacement = apply_proc(ctx, proc, vec![captures_to_list(&caps)])?;
+
+                result.push_str(&s[last..m.start()]);
+                result.push_str(&replacement.to_str()?);
+                last = m.end();
+            }
+
+            result.push_str(&s[last..]);
+            Ok(LispToken::Str(result))
+        }
+    }
+}
+
+// function: splits the string on every match of the regex.
+fn regex_split(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let re = to_regex(&xs[0])?;
+    let s = xs[1].to_str()?;
+
+    Ok(LispToken::List(re.search.split(&s).map(|part| LispToken::Str(part.to_string())).collect()))
+}
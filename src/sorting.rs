@@ -0,0 +1,214 @@
+use crate::context::{LispContext};
+use crate::eval::{apply_proc, eval_args, eval_vec, LispResult};
+use crate::hashtables::{HashTest, LispHash};
+use crate::lists::{to_list};
+use crate::tokens::{LispError, LispToken};
+use crate::vectors::{new_vector, to_vector};
+
+// Sorting and searching take the ordering as a procedure (less? a b), such as <, returning
+// true when a must come before b. Sorts are stable merge sorts, calling less? about
+// n log n times.
+
+// function: adds the sorting and searching functions to the given context.
+pub fn register(symbols: &mut LispContext) {
+    symbols.insert_func("sort", sort);
+    symbols.insert_func("sort!", sort_in_place);
+    symbols.insert_func("binary-search", binary_search);
+    symbols.insert_func("list-index", list_index);
+    symbols.insert_func("delete-duplicates", delete_duplicates);
+    symbols.insert_func("group-by", group_by);
+}
+
+fn less(ctx: &mut LispContext, proc: &LispToken, a: &LispToken, b: &LispToken) -> Result<bool, LispError> {
+    let result = apply_proc(ctx, proc, vec![a.clone(), b.clone()])?;
+    Ok(ctx.is_true(&result))
+}
+
+// function: sorts the items with a bottom up merge sort. an item of the right run is only
+// taken first when it is strictly less, which keeps equal items in their original order.
+fn merge_sort(ctx: &mut LispContext, items: Vec<LispToken>, proc: &LispToken) -> Result<Vec<LispToken>, LispError> {
+    let mut items = items;
+    let len = items.len();
+    let mut width = 1;
+
+    while width < len {
+        let mut merged = Vec::with_capacity(len);
+
+        for start in (0..len).step_by(2 * width) {
+            let mid = (start + width).min(len);
+            let end = (start + 2 * width).min(len);
+            let (mut i, mut j) = (start, mid);
+
+            while i < mid && j < end {
+                if less(ctx, proc, &items[j], &items[i])? {
+                    merged.push(items[j].clone());
+                    j += 1;
+                } else {
+                    merged.push(items[i].clone());
+                    i += 1;
+                }
+            }
+
+            merged.extend_from_slice(&items[i..mid]);
+            merged.extend_from_slice(&items[j..end]);
+        }
+
+        items = merged;
+        width *= 2;
+    }
+
+    Ok(items)
+}
+
+// function: returns a sorted copy of a list or vector.
+fn sort(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+
+    match &xs[0] {
+        LispToken::Vector(v) => {
+            let items = v.borrow().clone();
+            let sorted = merge_sort(ctx, items, &xs[1])?;
+            Ok(new_vector(ctx, sorted))
+        },
+        lst => {
+            let items = to_list(lst)?.to_vec();
+            Ok(LispToken::List(merge_sort(ctx, items, &xs[1])?))
+        }
+    }
+}
+
+// function: sorts a vector in place. lists are values that can't be changed, so for a list
+// the sorted list is returned instead.
+fn sort_in_place(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+
+    match &xs[0] {
+        LispToken::Vector(v) => {
+            let items = v.borrow().clone();
+            let sorted = merge_sort(ctx, items, &xs[1])?;
+            *v.borrow_mut() = sorted;
+
+            // memoized results may have read the old contents.
+            ctx.invalidate();
+            Ok(xs[0].clone())
+        },
+        lst => {
+            let items = to_list(lst)?.to_vec();
+            Ok(LispToken::List(merge_sort(ctx, items, &xs[1])?))
+        }
+    }
+}
+
+// function: finds the index of an item equal to the key in a list or vector sorted by less?,
+// or returns #f. items are equal when neither is less than the other.
+fn binary_search(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 3, 3)?;
+
+    let items = match &xs[0] {
+        LispToken::Vector(_) => to_vector(&xs[0])?.borrow().clone(),
+        lst => to_list(lst)?.to_vec()
+    };
+
+    let (mut low, mut high) = (0, items.len());
+
+    while low < high {
+        let mid = low + (high - low) / 2;
+
+        if less(ctx, &xs[2], &items[mid], &xs[1])? {
+            low = mid + 1;
+        } else if less(ctx, &xs[2], &xs[1], &items[mid])? {
+            high = mid;
+        } else {
+            return Ok(LispToken::from(mid as f64));
+        }
+    }
+
+    Ok(LispToken::from(false))
+}
+
+// function: returns the index of the first items of the lists for which the predicate is true, or #f.
+fn list_index(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_vec(ctx, args)?;
+
+    if xs.len() < 2 {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    let lists = xs[1..].iter().map(to_list).collect::<Result<Vec<_>, LispError>>()?;
+    let len = lists.iter().map(|lst| lst.len()).min().unwrap_or(0);
+
+    for idx in 0..len {
+        let call = lists.iter().map(|lst| lst[idx].clone()).collect();
+        let result = apply_proc(ctx, &xs[0], call)?;
+
+        if ctx.is_true(&result) {
+            return Ok(LispToken::from(idx as f64));
+        }
+    }
+
+    Ok(LispToken::from(false))
+}
+
+// function: returns the list keeping only the first of the items that are equal?, or equal
+// according to the procedure given as the second argument.
+fn delete_duplicates(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 2)?;
+    let lst = to_list(&xs[0])?;
+    let mut result : Vec<LispToken> = Vec::new();
+
+    match xs.get(1) {
+        Some(proc) => {
+            for x in lst {
+                let mut seen = false;
+
+                for kept in &result {
+                    let test = apply_proc(ctx, proc, vec![kept.clone(), x.clone()])?;
+
+                    if ctx.is_true(&test) {
+                        seen = true;
+                        break;
+                    }
+                }
+
+                if !seen {
+                    result.push(x.clone());
+                }
+            }
+        },
+        None => {
+            let mut seen = LispHash::new(HashTest::Equal);
+
+            for x in lst {
+                if seen.get(x).is_none() {
+                    seen.insert(x.clone(), LispToken::from(true));
+                    result.push(x.clone());
+                }
+            }
+        }
+    }
+
+    Ok(LispToken::List(result))
+}
+
+// function: groups the items of a list by the result of the key procedure, compared with
+// equal?. returns an association list of (key item ...) entries in order of first appearance.
+fn group_by(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let mut groups : Vec<(LispToken, Vec<LispToken>)> = Vec::new();
+    let mut index = LispHash::new(HashTest::Equal);
+
+    for x in to_list(&xs[1])? {
+        let key = apply_proc(ctx, &xs[0], vec![x.clone()])?;
+
+        match index.get(&key) {
+            Some(pos) => groups[pos.to_index()?].1.push(x.clone()),
+            None => {
+                index.insert(key.clone(), LispToken::from(groups.len() as f64));
+                groups.push((key, vec![x.clone()]));
+            }
+        }
+    }
+
+    let entries = groups.into_iter().map(|(key, items)| LispToken::cons(key, LispToken::List(items))).collect();
+    Ok(LispToken::List(entries))
+}
@@ -0,0 +1,487 @@
+use crate::context::{LispContext};
+use crate::eval::{apply_proc, eval_args, eval_vec, LispResult};
+use crate::tokens::{LispError, LispToken};
+
+use std::borrow::{Cow};
+use std::cell::{RefCell};
+use std::cmp::{Ordering};
+use std::convert::{TryFrom};
+use std::rc::{Rc};
+
+// Strings are sequences of characters, i.e. Unicode scalar values. Lengths and indices count
+// characters rather than bytes or grapheme clusters, so an "é" written as "e" followed by a
+// combining accent has a length of 2. Comparisons order strings by their code points.
+//
+// String literals are immutable. make-string and string-copy return mutable strings, which
+// can be changed in place by string-set! and string-fill!.
+
+// function: adds the string library to the given context.
+pub fn register(symbols: &mut LispContext) {
+    symbols.insert_func("string?", is_string);
+    symbols.insert_func("string-length", string_length);
+    symbols.insert_func("string-ref", string_ref);
+    symbols.insert_func("substring", substring);
+    symbols.insert_func("string-append", string_append);
+    symbols.insert_func("string-split", string_split);
+    symbols.insert_func("string-join", string_join);
+    symbols.insert_func("string-trim", string_trim);
+    symbols.insert_func("string-trim-left", string_trim_left);
+    symbols.insert_func("string-trim-right", string_trim_right);
+    symbols.insert_func("string-upcase", string_upcase);
+    symbols.insert_func("string-downcase", string_downcase);
+    symbols.insert_func("string-contains", string_contains);
+    symbols.insert_func("string-index", string_index);
+    symbols.insert_func("string-replace", string_replace);
+
+    symbols.insert_func("make-string", make_string);
+    symbols.insert_func("string-copy", string_copy);
+    symbols.insert_func("string-set!", string_set);
+    symbols.insert_func("string-fill!", string_fill);
+
+    symbols.insert_func("string->list", string_to_list);
+    symbols.insert_func("list->string", list_to_string);
+    symbols.insert_func("string->symbol", string_to_symbol);
+    symbols.insert_func("symbol->string", symbol_to_string);
+
+    symbols.insert_func("char?", is_char);
+    symbols.insert_func("char->integer", char_to_integer);
+    symbols.insert_func("integer->char", integer_to_char);
+    symbols.insert_func("char-alphabetic?", char_alphabetic);
+    symbols.insert_func("char-numeric?", char_numeric);
+    symbols.insert_func("char-whitespace?", char_whitespace);
+    symbols.insert_func("char-upcase", char_upcase);
+    symbols.insert_func("char-downcase", char_downcase);
+
+    symbols.insert_func("string=?", string_eq);
+    symbols.insert_func("string<?", string_lt);
+    symbols.insert_func("string>?", string_gt);
+    symbols.insert_func("string<=?", string_le);
+    symbols.insert_func("string>=?", string_ge);
+}
+
+// function: converts a char index into the byte offset of that char within the string.
+fn byte_offset(s: &str, idx: usize) -> Result<usize, LispError> {
+    if idx == s.chars().count() {
+        return Ok(s.len());
+    }
+
+    match s.char_indices().nth(idx) {
+        Some((offset, _)) => Ok(offset),
+        None => Err(LispError::EvalError("index out of range.".to_string()))
+    }
+}
+
+// function: converts a byte offset within the string into a char index.
+fn char_index(s: &str, offset: usize) -> usize {
+    s[..offset].chars().count()
+}
+
+// function: checks whether a value is a string, either immutable or mutable.
+fn is_string(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(LispToken::from(xs[0].to_str().is_ok()))
+}
+
+// function: returns the number of chars in a string.
+fn string_length(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(LispToken::from(xs[0].to_str()?.chars().count() as f64))
+}
+
+// function: returns the character at index k of a string.
+fn string_ref(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+
+    match xs[0].to_str()?.chars().nth(xs[1].to_index()?) {
+        Some(ch) => Ok(LispToken::Char(ch)),
+        None => Err(LispError::EvalError("index out of range.".to_string()))
+    }
+}
+
+// function: returns the part of a string between the optional start and end arguments.
+// start defaults to 0 and end to the length of the string.
+fn slice<'a>(s: &'a str, start: Option<&LispToken>, end: Option<&LispToken>) -> Result<&'a str, LispError> {
+    let start = match start {
+        Some(start) => byte_offset(s, start.to_index()?)?,
+        None => 0
+    };
+
+    let end = match end {
+        Some(end) => byte_offset(s, end.to_index()?)?,
+        None => s.len()
+    };
+
+    if start > end {
+        return Err(LispError::EvalError("index out of range.".to_string()));
+    }
+
+    Ok(&s[start..end])
+}
+
+// function: returns the chars from index start up to, but excluding, index end.
+// end defaults to the length of the string.
+fn substring(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 3)?;
+    let s = xs[0].to_str()?;
+    Ok(LispToken::Str(slice(&s, xs.get(1), xs.get(2))?.to_string()))
+}
+
+fn string_append(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_vec(ctx, args)?;
+    let mut result = String::new();
+
+    for x in &xs {
+        result.push_str(&x.to_str()?);
+    }
+
+    Ok(LispToken::Str(result))
+}
+
+// function: splits a string on every occurrence of the separator, or on runs of whitespace
+// when no separator is given, and returns the parts as a list.
+fn string_split(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 2)?;
+    let s = xs[0].to_str()?;
+
+    let parts : Vec<LispToken> = match xs.get(1) {
+        Some(sep) => {
+            let sep = sep.to_str()?;
+
+            if sep.is_empty() {
+                return Err(LispError::EvalError("separator is empty.".to_string()));
+            }
+
+            s.split(&*sep).map(|part| LispToken::Str(part.to_string())).collect()
+        },
+        None => s.split_whitespace().map(|part| LispToken::Str(part.to_string())).collect()
+    };
+
+    Ok(LispToken::List(parts))
+}
+
+// function: concatenates a list of strings, placing the separator between them.
+// the separator defaults to a single space.
+fn string_join(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 2)?;
+
+    let parts = match &xs[0] {
+        LispToken::List(parts) => parts,
+        _ => return Err(LispError::EvalError("value is not a list.".to_string()))
+    };
+
+    let sep = match xs.get(1) {
+        Some(sep) => sep.to_str()?,
+        None => Cow::Borrowed(" ")
+    };
+
+    let mut strs = Vec::new();
+    for part in parts {
+        strs.push(part.to_str()?);
+    }
+
+    Ok(LispToken::Str(strs.join(&*sep)))
+}
+
+// function: applies a conversion from one string to another to the single argument.
+fn map_string(ctx: &mut LispContext, args: &[LispToken], f: fn(&str) -> String) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(LispToken::Str(f(&xs[0].to_str()?)))
+}
+
+// function: removes leading and trailing whitespace.
+fn string_trim(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    map_string(ctx, args, |s| s.trim().to_string())
+}
+
+fn string_trim_left(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    map_string(ctx, args, |s| s.trim_start().to_string())
+}
+
+fn string_trim_right(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    map_string(ctx, args, |s| s.trim_end().to_string())
+}
+
+// function: converts to upper case using the full Unicode mapping, so the length may change ("ß" becomes "SS").
+fn string_upcase(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    map_string(ctx, args, str::to_uppercase)
+}
+
+// function: converts to lower case using the full Unicode mapping.
+fn string_downcase(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    map_string(ctx, args, str::to_lowercase)
+}
+
+// function: returns the index of the first occurrence of needle in the string, or #f.
+fn string_contains(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let s = xs[0].to_str()?;
+
+    match s.find(&*xs[1].to_str()?) {
+        Some(offset) => Ok(LispToken::from(char_index(&s, offset) as f64)),
+        None => Ok(LispToken::from(false))
+    }
+}
+
+// function: returns the index of the first character equal to the given one or satisfying
+// the given predicate, or #f when there is none.
+fn string_index(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let s = xs[0].to_str()?;
+
+    for (idx, ch) in s.chars().enumerate() {
+        let found = match &xs[1] {
+            LispToken::Char(target) => *target == ch,
+            pred => {
+                let value = apply_proc(ctx, pred, vec![LispToken::Char(ch)])?;
+                ctx.is_true(&value)
+            }
+        };
+
+        if found {
+            return Ok(LispToken::from(idx as f64));
+        }
+    }
+
+    Ok(LispToken::from(false))
+}
+
+// function: replaces every occurrence of pattern in the string with replacement.
+fn string_replace(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 3, 3)?;
+    let pattern = xs[1].to_str()?;
+
+    if pattern.is_empty() {
+        return Err(LispError::EvalError("pattern is empty.".to_string()));
+    }
+
+    Ok(LispToken::Str(xs[0].to_str()?.replace(&*pattern, &xs[2].to_str()?)))
+}
+
+// function: wraps the contents in a new mutable string. every call creates a distinct
+// object, so the result must not be memoized.
+fn mutable_string(ctx: &mut LispContext, s: String) -> LispToken {
+    ctx.record_effect();
+    LispToken::MutStr(Rc::new(RefCell::new(s)))
+}
+
+// function: returns the contents of a string that can be changed in place.
+fn to_mutable(token: &LispToken) -> Result<&Rc<RefCell<String>>, LispError> {
+    match token {
+        LispToken::MutStr(s) => Ok(s),
+        LispToken::Str(_) => Err(LispError::EvalError("string is immutable.".to_string())),
+        _ => Err(LispError::EvalError("value is not a string.".to_string()))
+    }
+}
+
+// function: returns a mutable string of k copies of the given character, or of spaces.
+fn make_string(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 2)?;
+
+    let ch = match xs.get(1) {
+        Some(ch) => ch.to_char()?,
+        None => ' '
+    };
+
+    let len = xs[0].to_index()?;
+    let mut s = String::new();
+
+    if len.checked_mul(ch.len_utf8()).is_none_or(|size| s.try_reserve(size).is_err()) {
+        return Err(LispError::EvalError(format!("cannot make a string of length {}.", xs[0])));
+    }
+
+    s.extend(std::iter::repeat_n(ch, len));
+    Ok(mutable_string(ctx, s))
+}
+
+// function: returns a mutable copy of the string between the optional start and end indices.
+fn string_copy(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 3)?;
+    let s = slice(&xs[0].to_str()?, xs.get(1), xs.get(2))?.to_string();
+    Ok(mutable_string(ctx, s))
+}
+
+// function: replaces the character at index k of a mutable string.
+fn string_set(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 3, 3)?;
+    let s = to_mutable(&xs[0])?;
+    let idx = xs[1].to_index()?;
+    let ch = xs[2].to_char()?;
+
+    let mut chars : Vec<char> = s.borrow().chars().collect();
+
+    if idx >= chars.len() {
+        return Err(LispError::EvalError("index out of range.".to_string()));
+    }
+
+    chars[idx] = ch;
+    *s.borrow_mut() = chars.into_iter().collect();
+
+    // memoized results may have read the old contents.
+    ctx.invalidate();
+    Ok(LispToken::nil())
+}
+
+// function: replaces the characters of a mutable string between the optional start and end indices.
+fn string_fill(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 4)?;
+    let s = to_mutable(&xs[0])?;
+    let ch = xs[1].to_char()?;
+
+    let filled = {
+        let contents = s.borrow();
+        let part = slice(&contents, xs.get(2), xs.get(3))?;
+        let start = part.as_ptr() as usize - contents.as_ptr() as usize;
+
+        let mut filled = contents[..start].to_string();
+        filled.extend(std::iter::repeat_n(ch, part.chars().count()));
+        filled.push_str(&contents[start + part.len()..]);
+        filled
+    };
+
+    *s.borrow_mut() = filled;
+
+    // memoized results may have read the old contents.
+    ctx.invalidate();
+    Ok(LispToken::nil())
+}
+
+// function: returns the characters of a string as a list.
+fn string_to_list(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    let chars = xs[0].to_str()?.chars().map(LispToken::Char).collect();
+    Ok(LispToken::List(chars))
+}
+
+// function: builds a string from a list of characters. strings in the list are appended as they are.
+fn list_to_string(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+
+    match &xs[0] {
+        LispToken::List(items) => {
+            let mut result = String::new();
+
+            for item in items {
+                match item {
+                    LispToken::Char(ch) => result.push(*ch),
+                    _ => result.push_str(&item.to_str()?)
+                }
+            }
+
+            Ok(LispToken::Str(result))
+        },
+        _ => Err(LispError::EvalError("value is not a list.".to_string()))
+    }
+}
+
+fn string_to_symbol(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(LispToken::Sym(xs[0].to_str()?.to_string()))
+}
+
+fn symbol_to_string(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+
+    match &xs[0] {
+        LispToken::Sym(s) => Ok(LispToken::Str(s.to_string())),
+        _ => Err(LispError::EvalError("value is not a symbol.".to_string()))
+    }
+}
+
+fn is_char(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(LispToken::from(xs[0].to_char().is_ok()))
+}
+
+// function: returns the Unicode code point of a character.
+fn char_to_integer(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(LispToken::from(xs[0].to_char()? as u32 as f64))
+}
+
+// function: returns the character with the given Unicode code point.
+fn integer_to_char(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+
+    match u32::try_from(xs[0].to_index()?).ok().and_then(std::char::from_u32) {
+        Some(ch) => Ok(LispToken::Char(ch)),
+        None => Err(LispError::EvalError("value is not a valid code point.".to_string()))
+    }
+}
+
+// function: applies a test on a character to the single argument.
+fn test_char(ctx: &mut LispContext, args: &[LispToken], test: fn(char) -> bool) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(LispToken::from(test(xs[0].to_char()?)))
+}
+
+fn char_alphabetic(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    test_char(ctx, args, char::is_alphabetic)
+}
+
+fn char_numeric(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    test_char(ctx, args, char::is_numeric)
+}
+
+fn char_whitespace(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    test_char(ctx, args, char::is_whitespace)
+}
+
+// function: applies a case mapping to a character, keeping it unchanged when the
+// mapping would produce more than one character.
+fn map_char<I: Iterator<Item = char>>(ch: char, mapped: I) -> LispToken {
+    let mapped : Vec<char> = mapped.collect();
+
+    match mapped.as_slice() {
+        [c] => LispToken::Char(*c),
+        _ => LispToken::Char(ch)
+    }
+}
+
+fn char_upcase(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    let ch = xs[0].to_char()?;
+    Ok(map_char(ch, ch.to_uppercase()))
+}
+
+fn char_downcase(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    let ch = xs[0].to_char()?;
+    Ok(map_char(ch, ch.to_lowercase()))
+}
+
+// function: checks that every pair of adjacent strings is ordered as accepted by the test.
+fn compare(ctx: &mut LispContext, args: &[LispToken], test: fn(Ordering) -> bool) -> LispResult {
+    let xs = eval_vec(ctx, args)?;
+
+    if xs.len() < 2 {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    for pair in xs.windows(2) {
+        if !test(pair[0].to_str()?.cmp(&pair[1].to_str()?)) {
+            return Ok(LispToken::from(false));
+        }
+    }
+
+    Ok(LispToken::from(true))
+}
+
+fn string_eq(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    compare(ctx, args, |ord| ord == Ordering::Equal)
+}
+
+fn string_lt(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    compare(ctx, args, |ord| ord == Ordering::Less)
+}
+
+fn string_gt(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    compare(ctx, args, |ord| ord == Ordering::Greater)
+}
+
+fn string_le(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    compare(ctx, args, |ord| ord != Ordering::Greater)
+}
+
+fn string_ge(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    compare(ctx, args, |ord| ord != Ordering::Less)
+}
@@ -1,12 +1,21 @@
 use crate::context::{LispContext};
+use crate::generics::{LispGeneric};
+use crate::hashtables::{LispHash};
+use crate::promises::{LispPromise};
+use crate::records::{LispRecord, LispRecordType};
+use crate::regexp::{LispRegex};
 use itertools::{Itertools};
+use std::borrow::{Cow};
+use std::cell::{RefCell};
 use std::convert::{From};
+use std::rc::{Rc};
 use std::{fmt};
 
 // enum: variant for storing possible errors that occur during parsing or eval processes.
 #[derive(Debug, PartialEq)]
 pub enum LispError {
     EndOfSequence,
+    Escape(usize, LispToken),
     EvalError(String),
     InvalidArguments,
     InvalidNoArguments,
@@ -41,15 +50,90 @@ impl fmt::Display for LispError {
     }
 }
 
+// names of the characters that can be written as #\name.
+const CHAR_NAMES: [(&str, char); 9] = [
+    ("alarm", '\x07'),
+    ("backspace", '\x08'),
+    ("delete", '\x7f'),
+    ("escape", '\x1b'),
+    ("newline", '\n'),
+    ("null", '\0'),
+    ("return", '\r'),
+    ("space", ' '),
+    ("tab", '\t')
+];
+
+// function: converts the text following #\ in a character literal to the character it names.
+// accepts a single character, one of the CHAR_NAMES or a hexadecimal code point such as x41.
+pub fn char_from_name(name: &str) -> Option<char> {
+    let mut chars = name.chars();
+
+    if let (Some(ch), None) = (chars.next(), chars.next()) {
+        return Some(ch);
+    }
+
+    if let Some((_, ch)) = CHAR_NAMES.iter().find(|(n, _)| *n == name) {
+        return Some(*ch);
+    }
+
+    if let Some(hex) = name.strip_prefix('x') {
+        return u32::from_str_radix(hex, 16).ok().and_then(std::char::from_u32);
+    }
+
+    None
+}
+
+// function: returns the text written after #\ for a character.
+fn char_name(ch: char) -> String {
+    match CHAR_NAMES.iter().find(|(_, c)| *c == ch) {
+        Some((name, _)) => name.to_string(),
+        None if ch.is_control() => format!("x{:x}", ch as u32),
+        None => ch.to_string()
+    }
+}
+
+// enum: destination written to by an output port.
+// Stdout: the standard output of the interpreter.
+// Str: a string output port accumulating everything written to it.
+pub enum LispPort {
+    Stdout,
+    Str(String)
+}
+
+impl LispPort {
+    pub fn write_str(&mut self, s: &str) {
+        match self {
+            LispPort::Stdout => print!("{}", s),
+            LispPort::Str(buffer) => buffer.push_str(s)
+        }
+    }
+}
+
+// type alias: signature shared by all builtin functions and special forms.
+pub type LispFunc = fn(&mut LispContext, &[LispToken]) -> Result<LispToken, LispError>;
+
 // enum: variant for storing the supported types in Lisp and serves as AST nodes.
 #[derive(Clone)]
 pub enum LispToken {
-    Func(fn(&mut LispContext, &Vec<Self>) -> Result<Self, LispError>),
+    Bytes(Rc<RefCell<Vec<u8>>>),
+    Char(char),
+    DottedList(Vec<Self>, Box<Self>),
+    Func(&'static str, LispFunc),
+    Generic(Rc<RefCell<LispGeneric>>),
+    Hash(Rc<RefCell<LispHash>>),
     List(Vec<Self>),
+    MutStr(Rc<RefCell<String>>),
     Num(String),
-    Quote(String),
+    Port(Rc<RefCell<LispPort>>),
+    Promise(Rc<RefCell<LispPromise>>),
+    Quote(Box<Self>),
+    Record(Rc<LispRecord>),
+    RecordType(Rc<LispRecordType>),
+    Regex(Rc<LispRegex>),
     Str(String),
-    Sym(String)
+    Sym(String),
+    Values(Vec<Self>),
+    Vector(Rc<RefCell<Vec<Self>>>)
 }
 
 impl LispToken {
@@ -60,29 +144,203 @@ impl LispToken {
         }
     }
 
-    pub fn to_bool(&self) -> Result<bool, LispError> {
-        if let LispToken::Sym(s) = self {
-            if s == "#t" {
-                return Ok(true);
-            } else if s == "#f" || s == "#nil" {
-                return Ok(false);
-            }
+    // function: returns the character of a char token.
+    pub fn to_char(&self) -> Result<char, LispError> {
+        match self {
+            LispToken::Char(ch) => Ok(*ch),
+            _ => Err(LispError::EvalError("value is not a character.".to_string()))
         }
-        return Err(LispError::EvalError("value is not a boolean.".to_string()));
     }
 
-    pub fn to_vec_bool(tokens: &Vec<LispToken>) -> Result<Vec<bool>, LispError> {
-        let mut xs = Vec::new();
+    // function: returns the contents of either an immutable or a mutable string.
+    pub fn to_str(&self) -> Result<Cow<'_, str>, LispError> {
+        match self {
+            LispToken::Str(s) => Ok(Cow::Borrowed(s)),
+            LispToken::MutStr(s) => Ok(Cow::Owned(s.borrow().clone())),
+            _ => Err(LispError::EvalError("value is not a string.".to_string()))
+        }
+    }
 
-        for token in tokens {
-            let b = token.to_bool()?;
-            xs.push(b);
+    // function: converts a non-negative integral number to an index.
+    pub fn to_index(&self) -> Result<usize, LispError> {
+        let f = self.to_float()?;
+
+        if f < 0.0 || f.fract() != 0.0 {
+            return Err(LispError::EvalError("value is not a valid index.".to_string()));
         }
 
-        Ok(xs)
+        Ok(f as usize)
+    }
+
+    // function: converts an integral number between 0 and 255 to a byte.
+    pub fn to_byte(&self) -> Result<u8, LispError> {
+        let f = self.to_float()?;
+
+        if !(0.0..=255.0).contains(&f) || f.fract() != 0.0 {
+            return Err(LispError::EvalError("value is not a byte.".to_string()));
+        }
+
+        Ok(f as u8)
+    }
+
+    // function: checks whether the token is the false boolean.
+    pub fn is_false(&self) -> bool {
+        self.is_sym("#f")
+    }
+
+    // function: returns the empty list, the single representation of nil.
+    pub fn nil() -> LispToken {
+        LispToken::List(Vec::new())
+    }
+
+    // function: checks whether the token is nil, i.e. the empty list.
+    pub fn is_nil(&self) -> bool {
+        match self {
+            LispToken::List(xs) => xs.is_empty(),
+            _ => false
+        }
+    }
+
+    // function: returns the pair of a head and a tail. lists are stored flat, so consing onto a
+    // list prepends to its items, while any other tail gives a dotted list such as (a . b).
+    pub fn cons(head: LispToken, tail: LispToken) -> LispToken {
+        match tail {
+            LispToken::List(mut xs) => {
+                xs.insert(0, head);
+                LispToken::List(xs)
+            },
+            LispToken::DottedList(mut xs, last) => {
+                xs.insert(0, head);
+                LispToken::DottedList(xs, last)
+            },
+            tail => LispToken::DottedList(vec![head], Box::new(tail))
+        }
+    }
+
+    // function: returns the head of a pair, or None for any other value.
+    pub fn car(&self) -> Option<&LispToken> {
+        match self {
+            LispToken::List(xs) | LispToken::DottedList(xs, _) => xs.first(),
+            _ => None
+        }
+    }
+
+    // function: returns the tail of a pair, or None for any other value.
+    pub fn cdr(&self) -> Option<LispToken> {
+        match self {
+            LispToken::List(xs) if !xs.is_empty() => Some(LispToken::List(xs[1..].to_vec())),
+            LispToken::DottedList(xs, last) if xs.len() == 1 => Some(*last.clone()),
+            LispToken::DottedList(xs, last) => Some(LispToken::DottedList(xs[1..].to_vec(), last.clone())),
+            _ => None
+        }
     }
 
-    pub fn to_vec_float(tokens: &Vec<LispToken>) -> Result<Vec<f64>, LispError> {
+    // function: checks whether the token is a pair, i.e. a non-empty list or a dotted list.
+    pub fn is_pair(&self) -> bool {
+        self.car().is_some()
+    }
+
+    pub fn is_sym(&self, name: &str) -> bool {
+        match self {
+            LispToken::Sym(s) => s == name,
+            _ => false
+        }
+    }
+
+    // function: checks whether the token is or holds an object compared by identity, whose printed
+    // form doesn't tell it apart from other objects with the same contents.
+    pub fn has_identity(&self) -> bool {
+        match self {
+            LispToken::Bytes(_) | LispToken::Generic(_) | LispToken::Hash(_) | LispToken::MutStr(_) |
+            LispToken::Port(_) | LispToken::Promise(_) | LispToken::Record(_) | LispToken::RecordType(_) |
+            LispToken::Regex(_) | LispToken::Vector(_) => true,
+            LispToken::List(xs) | LispToken::Values(xs) => xs.iter().any(|x| x.has_identity()),
+            LispToken::DottedList(xs, last) => xs.iter().any(|x| x.has_identity()) || last.has_identity(),
+            LispToken::Quote(x) => x.has_identity(),
+            _ => false
+        }
+    }
+
+    // function: identity equality used by eq?. symbols, booleans, procedures, mutable objects and
+    // the empty list are compared by identity and numbers by their written representation. lists are immutable
+    // values without an identity of their own, so they are eq? when their items are pairwise eqv?.
+    pub fn is_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (LispToken::List(a), LispToken::List(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.is_eqv(y))
+            },
+            (LispToken::DottedList(a, x), LispToken::DottedList(b, y)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(p, q)| p.is_eqv(q)) && x.is_eqv(y)
+            },
+            (LispToken::Bytes(a), LispToken::Bytes(b)) => Rc::ptr_eq(a, b),
+            (LispToken::Char(a), LispToken::Char(b)) => a == b,
+            (LispToken::Func(_, a), LispToken::Func(_, b)) => *a as usize == *b as usize,
+            (LispToken::Generic(a), LispToken::Generic(b)) => Rc::ptr_eq(a, b),
+            (LispToken::Hash(a), LispToken::Hash(b)) => Rc::ptr_eq(a, b),
+            (LispToken::MutStr(a), LispToken::MutStr(b)) => Rc::ptr_eq(a, b),
+            (LispToken::Num(a), LispToken::Num(b)) => a == b,
+            (LispToken::Port(a), LispToken::Port(b)) => Rc::ptr_eq(a, b),
+            (LispToken::Promise(a), LispToken::Promise(b)) => Rc::ptr_eq(a, b),
+            (LispToken::Quote(a), LispToken::Quote(b)) => a.is_eq(b),
+            (LispToken::Record(a), LispToken::Record(b)) => Rc::ptr_eq(a, b),
+            (LispToken::RecordType(a), LispToken::RecordType(b)) => Rc::ptr_eq(a, b),
+            (LispToken::Regex(a), LispToken::Regex(b)) => Rc::ptr_eq(a, b),
+            (LispToken::Vector(a), LispToken::Vector(b)) => Rc::ptr_eq(a, b),
+            (LispToken::Str(a), LispToken::Str(b)) => a == b,
+            (LispToken::Sym(a), LispToken::Sym(b)) => a == b,
+            _ => false
+        }
+    }
+
+    // function: value equality used by eqv?. extends eq? by comparing numbers by their value,
+    // so that 1 and 1.0 are eqv?.
+    pub fn is_eqv(&self, other: &Self) -> bool {
+        match (self, other) {
+            (LispToken::Num(a), LispToken::Num(b)) => match (a.parse::<f64>(), b.parse::<f64>()) {
+                (Ok(x), Ok(y)) => x == y,
+                _ => a == b
+            },
+            _ => self.is_eq(other)
+        }
+    }
+
+    // function: structural equality used by equal?. recursively compares the contents of lists,
+    // vectors, bytevectors, hash tables and strings, and falls back to eqv? for everything else.
+    pub fn is_equal(&self, other: &Self) -> bool {
+        match (self, other) {
+            (LispToken::Str(_), LispToken::MutStr(_)) |
+            (LispToken::MutStr(_), LispToken::Str(_)) |
+            (LispToken::MutStr(_), LispToken::MutStr(_)) => self.to_str().ok() == other.to_str().ok(),
+            (LispToken::Bytes(a), LispToken::Bytes(b)) => *a.borrow() == *b.borrow(),
+            (LispToken::DottedList(a, x), LispToken::DottedList(b, y)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(p, q)| p.is_equal(q)) && x.is_equal(y)
+            },
+            (LispToken::Hash(a), LispToken::Hash(b)) => {
+                let (a, b) = (a.borrow(), b.borrow());
+                a.len() == b.len() && a.entries().iter().all(|(k, v)| b.get(k).is_some_and(|w| v.is_equal(w)))
+            },
+            (LispToken::List(a), LispToken::List(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.is_equal(y))
+            },
+            (LispToken::Quote(a), LispToken::Quote(b)) => a.is_equal(b),
+            (LispToken::Regex(a), LispToken::Regex(b)) => a.pattern == b.pattern,
+            (LispToken::Values(a), LispToken::Values(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.is_equal(y))
+            },
+            (LispToken::Vector(a), LispToken::Vector(b)) => {
+                let (a, b) = (a.borrow(), b.borrow());
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| x.is_equal(y))
+            },
+            _ => self.is_eqv(other)
+        }
+    }
+
+    // function: returns a formatter printing the token in the given mode.
+    pub fn printed(&self, mode: PrintMode) -> Printer<'_> {
+        Printer(self, mode)
+    }
+
+    pub fn to_vec_float(tokens: &[LispToken]) -> Result<Vec<f64>, LispError> {
         let mut xs = Vec::new();
 
         for token in tokens {
@@ -115,8 +373,23 @@ impl From<bool> for LispToken {
 impl fmt::Debug for LispToken {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            LispToken::Func(_) => {
-                write!(f, "Fn<()>")
+            LispToken::Bytes(bytes) => {
+                write!(f, "Bytes({:?})", bytes.borrow())
+            },
+            LispToken::Char(ch) => {
+                write!(f, "Char({:?})", ch)
+            },
+            LispToken::DottedList(xs, last) => {
+                write!(f, "DottedList({:?}, {:?})", xs, last)
+            },
+            LispToken::Func(name, _) => {
+                write!(f, "Fn<{}>", name)
+            },
+            LispToken::Generic(generic) => {
+                write!(f, "Generic({})", generic.borrow().name)
+            },
+            LispToken::Hash(table) => {
+                write!(f, "Hash({:?})", table.borrow().entries())
             },
             LispToken::List(lst) => {
                 let xs = lst.iter().map(|v| format!("{:?}", v)).join(" ");
@@ -126,30 +399,89 @@ impl fmt::Debug for LispToken {
                     write!(f, "List([ {:?} ])", xs.trim_end())
                 }
             },
+            LispToken::MutStr(string) => {
+                write!(f, "MutStr({:?})", string.borrow())
+            },
             LispToken::Num(n) => {
                 write!(f, "Num({:?})", n)
             },
+            LispToken::Port(_) => {
+                write!(f, "Port")
+            },
+            LispToken::Promise(_) => {
+                write!(f, "Promise")
+            },
             LispToken::Quote(token) => {
-                write!(f, "Quote({:?})", token.trim_end())
-            }
+                write!(f, "Quote({:?})", token)
+            },
+            LispToken::Record(record) => {
+                write!(f, "Record({}, {:?})", record.rtype.name, record.values.borrow())
+            },
+            LispToken::RecordType(rtype) => {
+                write!(f, "RecordType({})", rtype.name)
+            },
+            LispToken::Regex(re) => {
+                write!(f, "Regex({:?})", re.pattern)
+            },
             LispToken::Str(string) => {
                 write!(f, "Str({:?})", string)
             },
             LispToken::Sym(string) => {
                 write!(f, "Sym(\"{}\")", string)
+            },
+            LispToken::Values(xs) => {
+                write!(f, "Values({:?})", xs)
+            },
+            LispToken::Vector(xs) => {
+                write!(f, "Vector({:?})", xs.borrow())
             }
         }
     }
 }
 
-impl fmt::Display for LispToken {
+// enum: selects how values are printed.
+// Display: strings and characters are printed as their raw contents, as done by display.
+// Write: strings are quoted and escaped and characters are written as #\ literals, so the
+// output reads back as the same value, as done by write.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PrintMode {
+    Display,
+    Write
+}
+
+// struct: formats a LispToken according to a PrintMode.
+pub struct Printer<'a>(&'a LispToken, PrintMode);
+
+impl fmt::Display for Printer<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            LispToken::Func(_) => {
-                write!(f, "Fn<()>")
+        let Printer(token, mode) = self;
+
+        match token {
+            LispToken::Bytes(bytes) => {
+                write!(f, "#u8({})", bytes.borrow().iter().join(" "))
+            },
+            LispToken::Char(ch) => match mode {
+                PrintMode::Display => write!(f, "{}", ch),
+                PrintMode::Write => write!(f, "#\\{}", char_name(*ch))
+            },
+            LispToken::DottedList(xs, last) => {
+                let xs = xs.iter().map(|v| format!("{}", v.printed(*mode))).join(" ");
+                write!(f, "({} . {})", xs, last.printed(*mode))
+            },
+            LispToken::Func(name, _) => {
+                write!(f, "#<procedure {}>", name)
+            },
+            LispToken::Generic(generic) => {
+                write!(f, "#<generic {}>", generic.borrow().name)
+            },
+            LispToken::Hash(table) => {
+                let entries = table.borrow().entries().iter()
+                    .map(|(k, v)| format!("{} {}", k.printed(*mode), v.printed(*mode)))
+                    .join(" ");
+                write!(f, "{{{}}}", entries)
             },
             LispToken::List(lst) => {
-                let xs = lst.iter().map(|v| format!("{}", v)).join(" ");
+                let xs = lst.iter().map(|v| format!("{}", v.printed(*mode))).join(" ");
                 if xs.is_empty() {
                     write!(f, "()")
                 } else {
@@ -159,28 +491,81 @@ impl fmt::Display for LispToken {
             LispToken::Num(n) => {
                 write!(f, "{}", n)
             },
+            LispToken::Port(port) => match &*port.borrow() {
+                LispPort::Stdout => write!(f, "#<output-port stdout>"),
+                LispPort::Str(_) => write!(f, "#<output-port string>")
+            },
+            LispToken::Promise(_) => {
+                write!(f, "#<promise>")
+            },
             LispToken::Quote(token) => {
-                write!(f, "{}", token)
+                write!(f, "'{}", token.printed(*mode))
             },
-            LispToken::Str(string) => {
-                write!(f, "{}", string)
+            LispToken::Record(record) => {
+                write!(f, "#<{}", record.rtype.name)?;
+
+                for (field, value) in record.rtype.fields.iter().zip(record.values.borrow().iter()) {
+                    write!(f, " {}: {}", field, value.printed(*mode))?;
+                }
+
+                write!(f, ">")
+            },
+            LispToken::RecordType(rtype) => {
+                write!(f, "#<record-type {}>", rtype.name)
+            },
+            LispToken::Regex(re) => {
+                write!(f, "#<regex \"{}\">", escape(&re.pattern))
+            },
+            LispToken::MutStr(string) => match mode {
+                PrintMode::Display => write!(f, "{}", string.borrow()),
+                PrintMode::Write => write!(f, "\"{}\"", escape(&string.borrow()))
+            },
+            LispToken::Str(string) => match mode {
+                PrintMode::Display => write!(f, "{}", string),
+                PrintMode::Write => write!(f, "\"{}\"", escape(string))
             },
             LispToken::Sym(string) => {
                 write!(f, "{}", string)
+            },
+            LispToken::Values(xs) => {
+                write!(f, "{}", xs.iter().map(|v| format!("{}", v.printed(*mode))).join(" "))
+            },
+            LispToken::Vector(xs) => {
+                let xs = xs.borrow().iter().map(|v| format!("{}", v.printed(*mode))).join(" ");
+                write!(f, "#({})", xs)
             }
         }
     }
 }
 
-// function: implements equality comparison for lisp primitives.
+// function: escapes the characters of a string that can't appear literally between quotes.
+fn escape(s: &str) -> String {
+    let mut escaped = String::new();
+
+    for ch in s.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            ch if ch.is_control() => escaped.push_str(&format!("\\x{:x};", ch as u32)),
+            ch => escaped.push(ch)
+        }
+    }
+
+    escaped
+}
+
+impl fmt::Display for LispToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.printed(PrintMode::Display))
+    }
+}
+
+// function: implements equality comparison for lisp primitives using equal? semantics.
 impl PartialEq for LispToken {
     fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (LispToken::Num(a), LispToken::Num(b)) => a == b,
-            (LispToken::Quote(a), LispToken::Quote(b)) => a == b,
-            (LispToken::Str(a), LispToken::Str(b)) => a == b,
-            (LispToken::Sym(a), LispToken::Sym(b)) => a == b,
-            _ => false
-        }
+        self.is_equal(other)
     }
 }
@@ -0,0 +1,102 @@
+use crate::context::{LispContext};
+use crate::eval::{apply_proc, eval_args, eval_vec, LispResult};
+use crate::tokens::{LispError, LispToken};
+
+// (values 1 2) returns two values at once. A single value is just that value, and any other
+// number of values is kept together until it reaches call-with-values, receive or let-values,
+// which hand each value to its own parameter. The REPL prints each value on its own line.
+
+// function: adds multiple values and the builtins returning them to the given context.
+pub fn register(symbols: &mut LispContext) {
+    symbols.insert_func("values", values);
+    symbols.insert_func("call-with-values", call_with_values);
+    symbols.insert_func("floor/", floor_div);
+    symbols.insert_func("truncate/", truncate_div);
+    symbols.insert_func("exact-integer-sqrt", exact_integer_sqrt);
+}
+
+// function: returns the token for the given values.
+pub(crate) fn make_values(mut xs: Vec<LispToken>) -> LispToken {
+    match xs.len() {
+        1 => xs.remove(0),
+        _ => LispToken::Values(xs)
+    }
+}
+
+// function: returns the values a token stands for, which is the token itself unless it holds
+// multiple values.
+pub(crate) fn values_of(token: LispToken) -> Vec<LispToken> {
+    match token {
+        LispToken::Values(xs) => xs,
+        token => vec![token]
+    }
+}
+
+fn values(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_vec(ctx, args)?;
+    Ok(make_values(xs))
+}
+
+// function: calls the producer without arguments and passes its values to the consumer.
+fn call_with_values(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let produced = apply_proc(ctx, &xs[0], Vec::new())?;
+    apply_proc(ctx, &xs[1], values_of(produced))
+}
+
+fn to_integer(token: &LispToken) -> Result<f64, LispError> {
+    let f = token.to_float()?;
+
+    if f.fract() != 0.0 || !f.is_finite() {
+        return Err(LispError::EvalError(format!("{} is not an integer.", token)));
+    }
+
+    Ok(f)
+}
+
+// function: returns the integers n and d of a division, checking that d isn't zero.
+fn division(ctx: &mut LispContext, args: &[LispToken]) -> Result<(f64, f64), LispError> {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let (n, d) = (to_integer(&xs[0])?, to_integer(&xs[1])?);
+
+    if d == 0.0 {
+        return Err(LispError::EvalError("division by zero.".to_string()));
+    }
+
+    Ok((n, d))
+}
+
+// function: returns the quotient rounded down and the remainder, which has the sign of the divisor.
+fn floor_div(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let (n, d) = division(ctx, args)?;
+    let q = (n / d).floor();
+    Ok(make_values(vec![LispToken::from(q), LispToken::from(n - q * d)]))
+}
+
+// function: returns the quotient rounded towards zero and the remainder, which has the sign of the dividend.
+fn truncate_div(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let (n, d) = division(ctx, args)?;
+    let q = (n / d).trunc();
+    Ok(make_values(vec![LispToken::from(q), LispToken::from(n - q * d)]))
+}
+
+// function: returns the largest s with s * s <= n and the remainder n - s * s.
+fn exact_integer_sqrt(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    let n = to_integer(&xs[0])?;
+
+    if n < 0.0 {
+        return Err(LispError::EvalError(format!("{} is negative.", xs[0])));
+    }
+
+    // the float square root may be off by one for large n.
+    let mut s = n.sqrt().floor();
+    while s * s > n {
+        s -= 1.0;
+    }
+    while (s + 1.0) * (s + 1.0) <= n {
+        s += 1.0;
+    }
+
+    Ok(make_values(vec![LispToken::from(s), LispToken::from(n - s * s)]))
+}
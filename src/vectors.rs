@@ -0,0 +1,221 @@
+use crate::context::{LispContext};
+use crate::eval::{apply_proc, eval_args, eval_vec, LispResult};
+use crate::tokens::{LispError, LispToken};
+
+use std::cell::{RefCell};
+use std::rc::{Rc};
+
+// Vectors are mutable, fixed length sequences with constant time, bounds checked indexing.
+// They are written as #(1 2 3), and their items are not evaluated when read.
+
+// function: adds the vector library to the given context.
+pub fn register(symbols: &mut LispContext) {
+    symbols.insert_func("vector?", is_vector);
+    symbols.insert_func("make-vector", make_vector);
+    symbols.insert_func("vector", vector);
+    symbols.insert_func("vector-length", vector_length);
+    symbols.insert_func("vector-ref", vector_ref);
+    symbols.insert_func("vector-set!", vector_set);
+    symbols.insert_func("vector->list", vector_to_list);
+    symbols.insert_func("list->vector", list_to_vector);
+    symbols.insert_func("vector-map", vector_map);
+    symbols.insert_func("vector-for-each", vector_for_each);
+    symbols.insert_func("vector-fill!", vector_fill);
+    symbols.insert_func("vector-grow", vector_grow);
+}
+
+// function: wraps the items in a new vector. every call creates a distinct object, so the
+// result must not be memoized.
+pub(crate) fn new_vector(ctx: &mut LispContext, xs: Vec<LispToken>) -> LispToken {
+    ctx.record_effect();
+    LispToken::Vector(Rc::new(RefCell::new(xs)))
+}
+
+pub(crate) fn to_vector(token: &LispToken) -> Result<&Rc<RefCell<Vec<LispToken>>>, LispError> {
+    match token {
+        LispToken::Vector(xs) => Ok(xs),
+        _ => Err(LispError::EvalError("value is not a vector.".to_string()))
+    }
+}
+
+// function: checks that an index refers to an item of a sequence of the given length.
+pub(crate) fn check_index(idx: usize, len: usize) -> Result<usize, LispError> {
+    if idx >= len {
+        return Err(LispError::EvalError(format!("index {} out of range for length {}.", idx, len)));
+    }
+
+    Ok(idx)
+}
+
+// function: reads the optional start and end indices of a range within a sequence of the given length.
+pub(crate) fn range(len: usize, start: Option<&LispToken>, end: Option<&LispToken>) -> Result<(usize, usize), LispError> {
+    let start = match start {
+        Some(start) => start.to_index()?,
+        None => 0
+    };
+
+    let end = match end {
+        Some(end) => end.to_index()?,
+        None => len
+    };
+
+    if start > end || end > len {
+        return Err(LispError::EvalError(format!("range {} to {} out of range for length {}.", start, end, len)));
+    }
+
+    Ok((start, end))
+}
+
+fn is_vector(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    Ok(LispToken::from(to_vector(&xs[0]).is_ok()))
+}
+
+// function: returns a vector of k items, all set to the fill value or #f.
+fn make_vector(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 2)?;
+
+    let fill = match xs.get(1) {
+        Some(fill) => fill.clone(),
+        None => LispToken::from(false)
+    };
+
+    let len = xs[0].to_index()?;
+    let mut items = Vec::new();
+
+    if items.try_reserve(len).is_err() {
+        return Err(LispError::EvalError(format!("cannot make a vector of length {}.", xs[0])));
+    }
+
+    items.resize(len, fill);
+    Ok(new_vector(ctx, items))
+}
+
+// function: returns a vector of the arguments.
+fn vector(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_vec(ctx, args)?;
+    Ok(new_vector(ctx, xs))
+}
+
+fn vector_length(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+    let len = to_vector(&xs[0])?.borrow().len();
+    Ok(LispToken::from(len as f64))
+}
+
+fn vector_ref(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let v = to_vector(&xs[0])?.borrow();
+    let idx = check_index(xs[1].to_index()?, v.len())?;
+    Ok(v[idx].clone())
+}
+
+fn vector_set(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 3, 3)?;
+
+    {
+        let mut v = to_vector(&xs[0])?.borrow_mut();
+        let idx = check_index(xs[1].to_index()?, v.len())?;
+        v[idx] = xs[2].clone();
+    }
+
+    // memoized results may have read the old contents.
+    ctx.invalidate();
+    Ok(LispToken::nil())
+}
+
+// function: returns the items of a vector between the optional start and end indices as a list.
+fn vector_to_list(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 3)?;
+    let v = to_vector(&xs[0])?.borrow();
+    let (start, end) = range(v.len(), xs.get(1), xs.get(2))?;
+    Ok(LispToken::List(v[start..end].to_vec()))
+}
+
+fn list_to_vector(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 1, 1)?;
+
+    match &xs[0] {
+        LispToken::List(items) => Ok(new_vector(ctx, items.to_vec())),
+        _ => Err(LispError::EvalError("value is not a list.".to_string()))
+    }
+}
+
+// function: reads the procedure and vectors passed to vector-map and vector-for-each, and
+// returns the argument lists for each call, stopping at the end of the shortest vector.
+fn eval_vector_args(ctx: &mut LispContext, args: &[LispToken]) -> Result<(LispToken, Vec<Vec<LispToken>>), LispError> {
+    let xs = eval_vec(ctx, args)?;
+
+    if xs.len() < 2 {
+        return Err(LispError::InvalidNoArguments);
+    }
+
+    let mut vectors = Vec::new();
+    for x in &xs[1..] {
+        vectors.push(to_vector(x)?.borrow().clone());
+    }
+
+    let len = vectors.iter().map(|v| v.len()).min().unwrap_or(0);
+    let calls = (0..len).map(|idx| vectors.iter().map(|v| v[idx].clone()).collect()).collect();
+
+    Ok((xs[0].clone(), calls))
+}
+
+// function: returns a new vector of the results of calling the procedure on the items of the vectors.
+fn vector_map(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let (proc, calls) = eval_vector_args(ctx, args)?;
+    let mut result = Vec::new();
+
+    for call in calls {
+        result.push(apply_proc(ctx, &proc, call)?);
+    }
+
+    Ok(new_vector(ctx, result))
+}
+
+// function: calls the procedure on the items of the vectors for its side effects.
+fn vector_for_each(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let (proc, calls) = eval_vector_args(ctx, args)?;
+
+    for call in calls {
+        apply_proc(ctx, &proc, call)?;
+    }
+
+    Ok(LispToken::nil())
+}
+
+// function: sets the items between the optional start and end indices to the fill value.
+fn vector_fill(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 4)?;
+
+    {
+        let mut v = to_vector(&xs[0])?.borrow_mut();
+        let (start, end) = range(v.len(), xs.get(2), xs.get(3))?;
+
+        for item in &mut v[start..end] {
+            *item = xs[1].clone();
+        }
+    }
+
+    // memoized results may have read the old contents.
+    ctx.invalidate();
+    Ok(LispToken::nil())
+}
+
+// function: returns a new vector of length k holding the items of the vector followed by #f.
+fn vector_grow(ctx: &mut LispContext, args: &[LispToken]) -> LispResult {
+    let xs = eval_args(ctx, args, 2, 2)?;
+    let mut items = to_vector(&xs[0])?.borrow().clone();
+    let len = xs[1].to_index()?;
+
+    if len < items.len() {
+        return Err(LispError::EvalError(format!("cannot shrink a vector of length {} to {}.", items.len(), len)));
+    }
+
+    if items.try_reserve(len - items.len()).is_err() {
+        return Err(LispError::EvalError(format!("cannot grow a vector to length {}.", xs[1])));
+    }
+
+    items.resize(len, LispToken::from(false));
+    Ok(new_vector(ctx, items))
+}